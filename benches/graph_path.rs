@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use sedeve_kit::data::path::_test::test_data_from_json;
+use sedeve_kit::trace::action_graph::{ActionGraph, OptPath, PathStrategy};
+
+// a chain of `n` states, which is as deep as the graph size
+fn chain_graph(n: i64) -> HashMap<i64, Vec<i64>> {
+    (1..n).map(|i| { (i, vec![i - 1]) }).collect()
+}
+
+// a random graph of `n` states with about `degree` edges out of each state, which has cycles
+fn random_graph(n: i64, degree: usize, seed: u64) -> HashMap<i64, Vec<i64>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n).map(|i| {
+        let mut vec: Vec<i64> = (0..degree).map(|_| { rng.gen_range(0..n) }).collect();
+        vec.sort();
+        vec.dedup();
+        (i, vec)
+    }).collect()
+}
+
+fn bench_graph_path(c: &mut Criterion) {
+    let mut graphs = vec![];
+    for (i, (adj, _, _)) in test_data_from_json::<HashMap<i64, Vec<i64>>, Vec<Vec<i64>>>(
+        "graph_find_path_1".to_string()).into_iter().enumerate() {
+        graphs.push((format!("graph_find_path_1/{}", i), adj));
+    }
+    graphs.push(("chain/100000".to_string(), chain_graph(100_000)));
+    graphs.push(("random/10000".to_string(), random_graph(10_000, 3, 1)));
+
+    let mut group = c.benchmark_group("build_path");
+    group.sample_size(10);
+    for strategy in [PathStrategy::Dfs, PathStrategy::EdgeCover, PathStrategy::Scc] {
+        let opt = OptPath {
+            strategy,
+            ..OptPath::default()
+        };
+        for (name, adj) in graphs.iter() {
+            let graph = ActionGraph::new(adj.clone());
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", strategy), name), &graph, |b, graph| {
+                    b.iter(|| {
+                        graph.build_path(&opt, &|_path| {}).unwrap();
+                    })
+                });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_graph_path);
+criterion_main!(benches);
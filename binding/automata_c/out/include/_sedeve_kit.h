@@ -39,6 +39,31 @@ void automata_close_input(const char *_name);
 /// Is an automata named `name` enable
 int32_t automata_enable(const char *_name);
 
+/// Report the tested node is paused(`_paused` is 1) or resumed(`_paused` is 0)
+/// `_name`, the automata's name
+void automata_pause(const char *_name, int32_t _paused);
+
+/// The fault on the next message the tested node sends, return 0 if sending the message as
+/// usual, 1 if dropping the message, 2 if sending the message twice
+/// `_name`, the automata's name
+/// `_dest_node_id`, dest node id of the message
+int32_t automata_message_fault(const char *_name, uint64_t _dest_node_id);
+
+/// The value the player picks for a nondeterministic choice, return 0 if the player picks a
+/// value, -1 if not, then the tested node makes the choice by itself
+/// `_name`, the automata's name
+/// `_choice_kind`, the kind of the choice, which is a key in the payload of an Internal action
+/// `_candidates_json`, the values to choose from in a JSON array, any value if it is empty
+/// `_output_buf_value_json`, output buffer of the value in JSON
+/// `_buf_len`, the buffer length
+/// `_output_len`, write bytes to output buffer
+int32_t automata_choose(const char *_name,
+                        const char *_choice_kind,
+                        const char *_candidates_json,
+                        char *_output_buf_value_json,
+                        uint64_t _buf_len,
+                        uint64_t *_output_len);
+
 /// Read next input action of automata, receive message from deterministic player
 /// `_name`, the automata's name
 /// `_output_source_node_id`, source node id
@@ -19,6 +19,10 @@ constexpr static const uint64_t C_ACTION_INTERNAL = 4;
 
 #ifdef ENABLE_DTM
 
+#define SETUP_ACTION(name, source, dest, action) { automata_action(name, C_ACTION_SETUP, C_ACTION_END, source, dest, action); }
+
+#define CHECK_ACTION(name, source, dest, state) { automata_action(name, C_ACTION_CHECK, C_ACTION_END, source, dest, state); }
+
 #define INPUT_ACTION(name, source, dest, action) { automata_action(name, C_ACTION_INPUT, C_ACTION_END, source, dest, action); }
 
 #define OUTPUT_ACTION(name, source, dest, action) { automata_action(name, C_ACTION_OUTPUT, C_ACTION_BEGIN, source, dest, action); }
@@ -30,6 +34,10 @@ constexpr static const uint64_t C_ACTION_INTERNAL = 4;
 
 #else
 
+#define SETUP_ACTION(_name, _source, _dest, _action) ;
+
+#define CHECK_ACTION(_name, _source, _dest, _state) ;
+
 #define INPUT_ACTION(_name, _source, _dest, _action)  ;
 
 #define OUTPUT_ACTION(_name, _source, _dest, _action) ;
@@ -3,6 +3,7 @@ use std::ffi::{c_char, CStr, CString};
 use num::FromPrimitive;
 use sedeve_kit::action::action_type::{ActionBeginEnd, ActionType};
 use sedeve_kit::dtm::automata;
+use sedeve_kit::dtm::fault::FaultKind;
 
 /// Set up an automata
 /// `_name`, the automata's name
@@ -81,6 +82,63 @@ pub extern "C" fn automata_enable(_name: *const c_char) -> i32 {
     }
 }
 
+/// Report the tested node is paused(`_paused` is 1) or resumed(`_paused` is 0)
+/// `_name`, the automata's name
+#[no_mangle]
+pub extern "C" fn automata_pause(_name: *const c_char, _paused: i32) {
+    let name = unsafe { CStr::from_ptr(_name) }.to_str().unwrap().to_string();
+    automata::automata_pause(name.as_str(), _paused != 0)
+}
+
+/// The fault on the next message the tested node sends, return 0 if sending the message as
+/// usual, 1 if dropping the message, 2 if sending the message twice
+/// `_name`, the automata's name
+/// `_dest_node_id`, dest node id of the message
+#[no_mangle]
+pub extern "C" fn automata_message_fault(_name: *const c_char, _dest_node_id: u64) -> i32 {
+    let name = unsafe { CStr::from_ptr(_name) }.to_str().unwrap().to_string();
+    match automata::automata_message_fault(name.as_str(), _dest_node_id) {
+        Some(FaultKind::Drop) => { 1 }
+        Some(FaultKind::Duplicate) => { 2 }
+        _ => { 0 }
+    }
+}
+
+/// The value the player picks for a nondeterministic choice, return 0 if the player picks a
+/// value, -1 if not, then the tested node makes the choice by itself
+/// `_name`, the automata's name
+/// `_choice_kind`, the kind of the choice, which is a key in the payload of an Internal action
+/// `_candidates_json`, the values to choose from in a JSON array, any value if it is empty
+/// `_output_buf_value_json`, output buffer of the value in JSON
+/// `_buf_len`, the buffer length
+/// `_output_len`, write bytes to output buffer
+#[no_mangle]
+pub extern "C" fn automata_choose(
+    _name: *const c_char,
+    _choice_kind: *const c_char,
+    _candidates_json: *const c_char,
+    _output_buf_value_json: *mut c_char,
+    _buf_len: u64,
+    _output_len: *mut u64
+) -> i32 {
+    let name = unsafe { CStr::from_ptr(_name) }.to_str().unwrap().to_string();
+    let kind = unsafe { CStr::from_ptr(_choice_kind) }.to_str().unwrap().to_string();
+    let candidates = unsafe { CStr::from_ptr(_candidates_json) }.to_str().unwrap().to_string();
+    let opt = automata::automata_choose_json(name.as_str(), kind.as_str(), candidates.as_str());
+    match opt {
+        Some(value) => {
+            let c_string = CString::new(value).unwrap();
+            let len = c_string.to_bytes().len();
+            if _buf_len as usize >= len {
+                unsafe { _output_buf_value_json.copy_from(c_string.as_ptr(), len) }
+            }
+            unsafe { *_output_len = len as u64; };
+            0
+        }
+        None => { -1 }
+    }
+}
+
 /// Read next input action of automata, receive message from deterministic player
 /// `_name`, the automata's name
 /// `_output_source_node_id`, source node id
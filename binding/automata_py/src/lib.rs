@@ -15,6 +15,16 @@ pub fn action_end() -> PyResult<u64> {
     Ok(action_type::C_ACTION_END)
 }
 
+#[pyfunction]
+pub fn action_setup() -> PyResult<u64> {
+    Ok(action_type::C_ACTION_SETUP)
+}
+
+#[pyfunction]
+pub fn action_check() -> PyResult<u64> {
+    Ok(action_type::C_ACTION_CHECK)
+}
+
 #[pyfunction]
 pub fn action_input() -> PyResult<u64> {
     Ok(action_type::C_ACTION_INPUT)
@@ -95,6 +105,33 @@ pub fn automata_enable(_name: String) -> bool {
     automata::automata_enable(_name.as_str())
 }
 
+/// Report the tested node is paused or resumed
+/// `_name`, the automata's name
+#[pyfunction]
+pub fn automata_pause(_name: String, _paused: bool) {
+    automata::automata_pause(_name.as_str(), _paused)
+}
+
+/// The fault on the next message the tested node sends, return "Drop" if dropping the
+/// message, "Duplicate" if sending the message twice, None if sending the message as usual
+/// `_name`, the automata's name
+/// `_dest_node_id`, dest node id of the message
+#[pyfunction]
+pub fn automata_message_fault(_name: String, _dest_node_id: u64) -> Option<String> {
+    automata::automata_message_fault(_name.as_str(), _dest_node_id)
+        .map(|k| { format!("{:?}", k) })
+}
+
+/// The value in JSON the player picks for a nondeterministic choice, None if the player picks
+/// no value, then the tested node makes the choice by itself
+/// `_name`, the automata's name
+/// `_choice_kind`, the kind of the choice, which is a key in the payload of an Internal action
+/// `_candidates_json`, the values to choose from in a JSON array, any value if it is empty
+#[pyfunction]
+pub fn automata_choose(_name: String, _choice_kind: String, _candidates_json: String) -> Option<String> {
+    automata::automata_choose_json(_name.as_str(), _choice_kind.as_str(), _candidates_json.as_str())
+}
+
 /// Read next input action of automata, receive message from deterministic player
 /// `_name`, the automata's name
 /// `_source_node_id`, source node id
@@ -153,6 +190,8 @@ pub fn automata_action(
 fn automata_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(action_begin, m)?)?;
     m.add_function(wrap_pyfunction!(action_end, m)?)?;
+    m.add_function(wrap_pyfunction!(action_setup, m)?)?;
+    m.add_function(wrap_pyfunction!(action_check, m)?)?;
     m.add_function(wrap_pyfunction!(action_input, m)?)?;
     m.add_function(wrap_pyfunction!(action_internal, m)?)?;
     m.add_function(wrap_pyfunction!(action_output, m)?)?;
@@ -161,6 +200,9 @@ fn automata_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(automata_setup_with_input, m)?)?;
     m.add_function(wrap_pyfunction!(automata_clear, m)?)?;
     m.add_function(wrap_pyfunction!(automata_enable, m)?)?;
+    m.add_function(wrap_pyfunction!(automata_pause, m)?)?;
+    m.add_function(wrap_pyfunction!(automata_message_fault, m)?)?;
+    m.add_function(wrap_pyfunction!(automata_choose, m)?)?;
     m.add_function(wrap_pyfunction!(automata_next_input, m)?)?;
     m.add_function(wrap_pyfunction!(automata_action, m)?)?;
     Ok(())
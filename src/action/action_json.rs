@@ -101,6 +101,46 @@ impl ActionJson {
         })
     }
 
+    /// The payload of the action message
+    pub fn payload(&self) -> Res<Value> {
+        MessageJson::new(self.message_payload_json_value()?).payload()
+    }
+
+    /// The same action with another payload
+    pub fn with_payload(&self, payload: &Value) -> Res<Self> {
+        Self::from_json(
+            self.action_type()?,
+            self.source_nid()?,
+            self.dest_nid()?,
+            payload.to_string())
+    }
+
+    /// The name of the action, which is the typed enum names of the message payload joined by
+    /// `::`, such as `DTMTesting::HandleAppendLog`
+    pub fn action_name(&self) -> Res<String> {
+        let payload = MessageJson::new(self.message_payload_json_value()?).payload()?;
+        let mut names = vec![];
+        let mut value = &payload;
+        loop {
+            match value {
+                Value::String(s) => {
+                    names.push(s.clone());
+                    break;
+                }
+                Value::Object(map) if map.len() == 1 => {
+                    let (k, v) = map.iter().next().unwrap();
+                    if !k.starts_with(|c: char| { c.is_ascii_uppercase() }) {
+                        break;
+                    }
+                    names.push(k.clone());
+                    value = v;
+                }
+                _ => { break; }
+            }
+        }
+        Ok(names.join("::"))
+    }
+
     fn handle_object_one_key_value<F, R>(&self, f: F) -> Res<R>
         where F: Fn(&String, &Value) -> Res<R>
     {
@@ -239,6 +279,7 @@ mod test {
         a1.hash(&mut h1);
         a2.hash(&mut h2);
         assert_eq!(h1.finish(), h2.finish(), "test_action_map_order hash value not equal");
+        assert_eq!(a1.action_name().unwrap(), "DTMTesting::HandleAppendLog");
         println!("end test_action_map_order");
     }
 }
\ No newline at end of file
@@ -26,6 +26,14 @@ Encode,
 pub enum ActionMessage<
     Payload: MsgTrait + 'static,
 > {
+    /// Represent a node receiving an initial state snapshot.
+    #[serde(bound = "Payload: MsgTrait")]
+    Setup(Message<Payload>),
+
+    /// Represent a node receiving the expected state, and reporting the state it observes.
+    #[serde(bound = "Payload: MsgTrait")]
+    Check(Message<Payload>),
+
     /// Represent a node receiving an input message, from a network endpoint or a terminal, for example.
     /// When check_all_begin_end disable, the driver would expected to send only end input action.
     #[serde(bound = "Payload: MsgTrait")]
@@ -39,6 +47,10 @@ pub enum ActionMessage<
     /// Represent an internal event in a node
     #[serde(bound = "Payload: MsgTrait")]
     Internal(Message<Payload>),
+
+    /// Represent a fault injected to the system, enacted by the deterministic player
+    #[serde(bound = "Payload: MsgTrait")]
+    Fault(Message<Payload>),
 }
 
 impl<
@@ -49,9 +61,12 @@ impl<Payload: MsgTrait + 'static> ActionMessage<Payload> {
     /// Build an ActionMessage through ActionType and Message struct.
     pub fn from_message(action_type: ActionType, message: Message<Payload>) -> Self {
         match action_type {
+            ActionType::Setup => { ActionMessage::Setup(message) }
+            ActionType::Check => { ActionMessage::Check(message) }
             ActionType::Input => { ActionMessage::Input(message) }
             ActionType::Internal => { ActionMessage::Internal(message) }
             ActionType::Output => { ActionMessage::Output(message) }
+            ActionType::Fault => { ActionMessage::Fault(message) }
         }
     }
 
@@ -81,18 +96,24 @@ impl<Payload: MsgTrait + 'static> ActionMessage<Payload> {
     /// Action type
     pub fn action_type(&self) -> ActionType {
         match self {
+            ActionMessage::Setup(_) => { ActionType::Setup }
+            ActionMessage::Check(_) => { ActionType::Check }
             ActionMessage::Input(_) => { ActionType::Input }
             ActionMessage::Output(_) => { ActionType::Output }
             ActionMessage::Internal(_) => { ActionType::Internal }
+            ActionMessage::Fault(_) => { ActionType::Fault }
         }
     }
 
     fn fn_message<F, R>(&self, f: F) -> Res<R>
         where F: Fn(&Message<Payload>) -> Res<R> {
         match self {
+            ActionMessage::Setup(m) => { f(m) }
+            ActionMessage::Check(m) => { f(m) }
             ActionMessage::Input(m) => { f(m) }
             ActionMessage::Output(m) => { f(m) }
             ActionMessage::Internal(m) => { f(m) }
+            ActionMessage::Fault(m) => { f(m) }
         }
     }
 
@@ -9,6 +9,7 @@ use crate::action::constant;
 pub const C_ACTION_BEGIN: u64 = 0;
 pub const C_ACTION_END: u64 = 1;
 
+// the value of an ActionType, which can be converted by `FromPrimitive`
 pub const C_ACTION_SETUP: u64 = 0;
 pub const C_ACTION_CHECK: u64 = 1;
 pub const C_ACTION_INPUT: u64 = 2;
@@ -32,14 +33,25 @@ Encode,
 )]
 pub enum ActionType {
 
+    /// Represent a node receiving an initial state snapshot, which the node sets up as its state
+    Setup = 0,
+
+    /// Represent a node receiving the state expected by the specification, the node reports the
+    /// state it observes, and the player compares the two states
+    Check = 1,
+
     /// Represent a node receiving an input message, from a network endpoint or a terminal, for example
-    Input = 1,
+    Input = 2,
 
     /// Represent a node sending an output message, to a network endpoint or a terminal, for example
-    Output = 2,
+    Output = 3,
 
     /// Represent an internal event in a node
-    Internal = 3,
+    Internal = 4,
+
+    /// Represent a fault injected to the system, such as a node crash or a message loss,
+    /// which is enacted by the deterministic player rather than performed by a node
+    Fault = 5,
 }
 
 #[derive(
@@ -66,8 +78,9 @@ impl ActionType {
             constant::ACTION_TYPE_OUTPUT => { ActionType::Output }
             constant::ACTION_TYPE_INPUT => { ActionType::Input }
             constant::ACTION_TYPE_INTERNAL => { ActionType::Internal }
-            constant::ACTION_TYPE_SETUP => { ActionType::Input }
-            constant::ACTION_TYPE_CHECK => { ActionType::Input }
+            constant::ACTION_TYPE_SETUP => { ActionType::Setup }
+            constant::ACTION_TYPE_CHECK => { ActionType::Check }
+            constant::ACTION_TYPE_FAULT => { ActionType::Fault }
             _ => { panic!("unknown TLA+ action type error") }
         }
     }
@@ -77,26 +90,64 @@ impl ActionType {
             constant::SERDE_ACTION_TYPE_OUTPUT => { ActionType::Output }
             constant::SERDE_ACTION_TYPE_INPUT => { ActionType::Input }
             constant::SERDE_ACTION_TYPE_INTERNAL => { ActionType::Internal }
-            constant::SERDE_ACTION_TYPE_SETUP => { ActionType::Input }
-            constant::SERDE_ACTION_TYPE_CHECK => { ActionType::Input }
+            constant::SERDE_ACTION_TYPE_SETUP => { ActionType::Setup }
+            constant::SERDE_ACTION_TYPE_CHECK => { ActionType::Check }
+            constant::SERDE_ACTION_TYPE_FAULT => { ActionType::Fault }
             _ => { panic!("unknown serde action type error") }
         }
     }
 
+    /// The player delivers the message of the action to the node, the node performs the action
+    /// after receiving the message
+    pub fn is_delivered_to_node(&self) -> bool {
+        matches!(self, ActionType::Setup | ActionType::Check | ActionType::Input)
+    }
+
     pub fn action_message<P: MsgTrait + 'static>(&self, m: Message<P>) -> ActionMessage<P> {
         match self {
+            ActionType::Setup => { ActionMessage::Setup(m) }
+            ActionType::Check => { ActionMessage::Check(m) }
             ActionType::Input => { ActionMessage::Input(m) }
             ActionType::Internal => { ActionMessage::Internal(m) }
             ActionType::Output => { ActionMessage::Output(m) }
+            ActionType::Fault => { ActionMessage::Fault(m) }
         }
     }
 
     pub fn to_string(&self) -> String {
         let s = match self {
+            ActionType::Setup => { constant::SERDE_ACTION_TYPE_SETUP.to_string() }
+            ActionType::Check => { constant::SERDE_ACTION_TYPE_CHECK.to_string() }
             ActionType::Input => { constant::SERDE_ACTION_TYPE_INPUT.to_string() }
             ActionType::Internal => { constant::SERDE_ACTION_TYPE_INTERNAL.to_string() }
             ActionType::Output => { constant::SERDE_ACTION_TYPE_OUTPUT.to_string() }
+            ActionType::Fault => { constant::SERDE_ACTION_TYPE_FAULT.to_string() }
         };
         s
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use num::FromPrimitive;
+
+    use crate::action::action_type::{ActionType, C_ACTION_CHECK, C_ACTION_INPUT, C_ACTION_INTERNAL, C_ACTION_OUTPUT, C_ACTION_SETUP};
+    use crate::action::constant;
+
+    #[test]
+    fn test_action_type() {
+        for (c, t) in [
+            (C_ACTION_SETUP, ActionType::Setup),
+            (C_ACTION_CHECK, ActionType::Check),
+            (C_ACTION_INPUT, ActionType::Input),
+            (C_ACTION_OUTPUT, ActionType::Output),
+            (C_ACTION_INTERNAL, ActionType::Internal),
+        ] {
+            let opt: Option<ActionType> = FromPrimitive::from_u64(c);
+            assert_eq!(opt, Some(t));
+            assert_eq!(ActionType::from_serde_action_type(t.to_string().as_str()), t);
+        }
+        assert_eq!(ActionType::from_tla_action_type(constant::ACTION_TYPE_SETUP), ActionType::Setup);
+        assert_eq!(ActionType::from_tla_action_type(constant::ACTION_TYPE_CHECK), ActionType::Check);
+    }
+}
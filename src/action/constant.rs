@@ -32,6 +32,8 @@ pub const ACTION_TYPE_INPUT: &str = "I";
 pub const ACTION_TYPE_OUTPUT: &str = "O";
 /// Internal action type in .tla+
 pub const ACTION_TYPE_INTERNAL: &str = "T";
+/// Fault action type in .tla+
+pub const ACTION_TYPE_FAULT: &str = "F";
 
 
 pub const SERDE_ACTION_TYPE_SETUP: &str = "Setup";
@@ -39,6 +41,7 @@ pub const SERDE_ACTION_TYPE_CHECK: &str = "Check";
 pub const SERDE_ACTION_TYPE_INPUT: &str = "Input";
 pub const SERDE_ACTION_TYPE_OUTPUT: &str = "Output";
 pub const SERDE_ACTION_TYPE_INTERNAL: &str = "Internal";
+pub const SERDE_ACTION_TYPE_FAULT: &str = "Fault";
 
 
 pub const ACTION_NAME_SEPARATOR: &str = "::";
@@ -0,0 +1,57 @@
+use std::fs::write;
+
+use clap::Parser;
+use scupt_util::init_logger::logger_setup;
+use scupt_util::res::Res;
+use scupt_util::res_of::res_io;
+use sedeve_kit::trace::coverage::{CoverageReport, CoverageScope};
+use sedeve_kit::trace::trace_db::TraceDB;
+use sedeve_kit::trace::trace_db_interm::TraceDBInterm;
+
+/// Report which states and edges of the state graph of the specification are covered by the traces
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path of the intermediate action database output by sedeve_trace_gen, which has the `action`
+    /// and `path` tables
+    #[arg(short, long)]
+    action_db_path: String,
+
+    /// Path of the trace database, which has the results of the traces run by the player
+    #[arg(short = 'd', long)]
+    trace_db_path: Option<String>,
+
+    /// The traces counted, `passed` by default if the trace database is given, otherwise,
+    /// `generated`
+    #[arg(short, long)]
+    scope: Option<CoverageScope>,
+
+    /// Also write the report in JSON to this path
+    #[arg(short, long)]
+    json_path: Option<String>,
+}
+
+fn coverage(args: &Args) -> Res<CoverageReport> {
+    let interm = TraceDBInterm::new(args.action_db_path.clone(), None, None)?;
+    let opt_db = match &args.trace_db_path {
+        Some(path) => { Some(TraceDB::new(path.clone())?) }
+        None => { None }
+    };
+    let scope = args.scope.unwrap_or(if opt_db.is_some() {
+        CoverageScope::Passed
+    } else {
+        CoverageScope::Generated
+    });
+    let report = CoverageReport::new(&interm, opt_db.as_ref(), scope)?;
+    if let Some(path) = &args.json_path {
+        res_io(write(path, report.to_json()?))?;
+    }
+    Ok(report)
+}
+
+fn main() {
+    let args = Args::parse();
+    logger_setup();
+    let report = coverage(&args).unwrap();
+    println!("{}", report);
+}
@@ -1,22 +1,40 @@
-use std::process::exit;
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
 
+use scupt_util::error_type::ET;
 use scupt_util::message::Message;
 use scupt_util::node_id::NID;
 use scupt_util::res::Res;
+use serde_json::Value;
+use tokio::sync::Mutex;
 use tokio::sync::mpsc::UnboundedSender;
-use tokio::time::sleep;
 use tracing::{error, trace};
 
 use crate::action::action_json::ActionJson;
 use crate::action::action_type::ActionType;
 use crate::dtm::action_reorder::ActionReorder;
+use crate::dtm::choice::ChoiceOffers;
+use crate::dtm::divergence_report::{DivergenceReport, QueuePending};
+use crate::dtm::fault::Fault;
+use crate::dtm::liveness::Liveness;
 use crate::dtm::msg_ctrl::MessageControl;
+use crate::dtm::state_projection::StateProjection;
+use crate::dtm::trace_verdict::{TraceStep, TraceVerdict};
+use crate::dtm::virtual_clock::VirtualClock;
+
+// an action requested by a tested node, which timeout waiting for the trace, the node is blocked
+// until the ACK is sent
+struct TimeoutAction {
+    action: ActionJson,
+    ack: Message<MessageControl>,
+    ch_sender: UnboundedSender<Message<MessageControl>>,
+}
 
 struct ActionExecutorInner {
-    seconds_timeout: u64,
+    liveness: Liveness,
     wait_both_begin_and_end_action: bool,
+    // the states of Check actions are compared by their projections
+    state_projection: Option<Arc<dyn StateProjection>>,
     trace_wait_input_begin: ActionReorder,
     trace_wait_input_end: ActionReorder,
     node_wait_input: ActionReorder,
@@ -28,6 +46,18 @@ struct ActionExecutorInner {
     trace_wait_internal_begin: ActionReorder,
     trace_wait_internal_end: ActionReorder,
     node_wait_internal: ActionReorder,
+
+    // the Internal actions being waited for, whose payloads pick the values of the choices
+    choices: ChoiceOffers,
+
+    // advanced by the Tick actions of the trace
+    clock: VirtualClock,
+
+    // actions requested by the tested nodes, which timeout waiting for the trace
+    node_timeout_actions: Mutex<Vec<TimeoutAction>>,
+
+    // message faults not delivered yet, which are delivered to a node with the next ACK to it
+    pending_faults: Mutex<HashMap<NID, Vec<Fault>>>,
 }
 
 #[derive(Clone)]
@@ -38,31 +68,61 @@ pub struct ActionExecutor {
 impl ActionExecutor {
     pub fn new(
         wait_both_begin_and_end_action: bool,
-        seconds_timeout: u64,
+        state_projection: Option<Arc<dyn StateProjection>>,
+        liveness: Liveness,
     ) -> Self {
         Self {
             inner: Arc::new(ActionExecutorInner {
-                seconds_timeout,
+                liveness: liveness.clone(),
                 wait_both_begin_and_end_action,
-                trace_wait_input_begin: ActionReorder::new(seconds_timeout),
-                trace_wait_input_end: ActionReorder::new(seconds_timeout),
-                node_wait_input: ActionReorder::new(seconds_timeout),
-                trace_wait_output_begin: ActionReorder::new(seconds_timeout),
-                trace_wait_output_end: ActionReorder::new(seconds_timeout),
-                node_wait_output: ActionReorder::new(seconds_timeout),
-                trace_wait_internal_begin: ActionReorder::new(seconds_timeout),
-                trace_wait_internal_end: ActionReorder::new(seconds_timeout),
-                node_wait_internal: ActionReorder::new(seconds_timeout),
+                state_projection,
+                trace_wait_input_begin: ActionReorder::new(liveness.clone()),
+                trace_wait_input_end: ActionReorder::new(liveness.clone()),
+                node_wait_input: ActionReorder::new(liveness.clone()),
+                trace_wait_output_begin: ActionReorder::new(liveness.clone()),
+                trace_wait_output_end: ActionReorder::new(liveness.clone()),
+                node_wait_output: ActionReorder::new(liveness.clone()),
+                trace_wait_internal_begin: ActionReorder::new(liveness.clone()),
+                trace_wait_internal_end: ActionReorder::new(liveness.clone()),
+                node_wait_internal: ActionReorder::new(liveness.clone()),
+                choices: ChoiceOffers::new(liveness.clone()),
+                clock: Default::default(),
+                node_timeout_actions: Default::default(),
+                pending_faults: Default::default(),
             }),
         }
     }
 
 
+    pub fn liveness(&self) -> &Liveness {
+        &self.inner.liveness
+    }
+
+    pub fn clock(&self) -> &VirtualClock {
+        &self.inner.clock
+    }
+
+    /// Deliver a message fault to its source node
+    pub async fn add_message_fault(&self, fault: Fault) {
+        let mut pending = self.inner.pending_faults.lock().await;
+        pending.entry(fault.source).or_default().push(fault);
+    }
+
+    /// The value the trace picks for the choice `kind` of node `node_id`, see `ChoiceOffers`
+    pub async fn choose(&self, node_id: NID, kind: &str, candidates: &[Value]) -> Res<Option<Value>> {
+        let opt = self.inner.choices.choose(node_id, kind, candidates).await?;
+        if opt.is_none() {
+            error!("timeout, node {} choose {} from {:?}", node_id, kind, candidates);
+        }
+        Ok(opt)
+    }
+
     pub async fn expect_node_sync(&self, action: &ActionJson) -> Res<bool> {
         let action_type = action.action_type()?;
+        let action = &self.project_state(action)?;
         let msg = action.serde_json_value_ref();
         match action_type {
-            ActionType::Input
+            ActionType::Setup | ActionType::Check | ActionType::Input
             => {
                 trace!("INPUT: notify case file input {:?}", msg);
                 self.inner.node_wait_input.add_action(action).await?;
@@ -97,10 +157,12 @@ impl ActionExecutor {
             }
             ActionType::Internal => {
                 trace!("INPUT: notify case file input {:?}", msg);
+                self.inner.choices.offer(action);
                 self.inner.node_wait_internal.add_action(action).await?;
 
                 trace!("INPUT: wait receive from node begin {:?}", msg);
                 let ok = self.inner.trace_wait_internal_begin.wait_action(action).await?;
+                self.inner.choices.withdraw(action);
                 if !ok {
                     return Ok(false);
                 }
@@ -110,6 +172,11 @@ impl ActionExecutor {
                     return Ok(false);
                 }
             }
+            ActionType::Fault => {
+                error!("a fault action is enacted by the player, not performed by nodes, {:?}", action);
+                return Err(ET::ParseError(format!(
+                    "fault action cannot be expected from nodes, it is enacted by the player, {:?}", action)));
+            }
         }
 
         Ok(true)
@@ -131,13 +198,24 @@ impl ActionExecutor {
             } else {
                 "end"
             };
+            // do not ACK, the tested node would be blocked until the player reports the verdict
             error!("message timeout, expect wait {} action {:?} in trace", str, action);
-            sleep(Duration::from_secs(self.inner.seconds_timeout)).await;
-            exit(-1);
+            let ack = Message::new(
+                MessageControl::ActionACK { id, faults: vec![] },
+                dest,
+                source);
+            self.inner.node_timeout_actions.lock().await.push(TimeoutAction {
+                action,
+                ack,
+                ch_sender,
+            });
         } else {
             // ACK of the requested action
+            let faults = self.inner.pending_faults.lock().await
+                .remove(&source)
+                .unwrap_or_default();
             let response = Message::new(
-                MessageControl::ActionACK { id },
+                MessageControl::ActionACK { id, faults },
                 dest,
                 source);
             trace!("send response {:?}", response);
@@ -155,11 +233,106 @@ impl ActionExecutor {
         Ok(())
     }
 
+    /// ACK the actions which timeout waiting for the trace, after the verdict is made, so the
+    /// blocked nodes go on
+    pub async fn release_timeout_actions(&self) {
+        let actions = std::mem::take(&mut *self.inner.node_timeout_actions.lock().await);
+        for a in actions {
+            trace!("send response of timeout action {:?}", a.action);
+            if let Err(e) = a.ch_sender.send(a.ack) {
+                error!("response action {:?} , error : {}" , a.action, e.to_string())
+            }
+        }
+    }
+
+    /// The verdict when timeout waiting for the `expected` action at `step`.
+    /// The trace diverged if the tested nodes performed some actions not expected at this time.
+    pub async fn timeout_verdict(
+        &self,
+        trace_index: u64,
+        step: TraceStep,
+        expected: ActionJson,
+    ) -> TraceVerdict {
+        let report = self.divergence_report(expected.clone()).await;
+        if report.has_unmatched() {
+            TraceVerdict::Diverged {
+                trace_index,
+                step,
+                expected,
+                report,
+            }
+        } else {
+            TraceVerdict::Timeout {
+                trace_index,
+                step,
+                expected,
+                report,
+            }
+        }
+    }
+
+    pub async fn divergence_report(&self, expected: ActionJson) -> DivergenceReport {
+        let inner = &self.inner;
+        let mut pending = vec![];
+        for (name, r) in [
+            ("trace_wait_input_begin", &inner.trace_wait_input_begin),
+            ("trace_wait_input_end", &inner.trace_wait_input_end),
+            ("node_wait_input", &inner.node_wait_input),
+            ("trace_wait_output_begin", &inner.trace_wait_output_begin),
+            ("trace_wait_output_end", &inner.trace_wait_output_end),
+            ("node_wait_output", &inner.node_wait_output),
+            ("trace_wait_internal_begin", &inner.trace_wait_internal_begin),
+            ("trace_wait_internal_end", &inner.trace_wait_internal_end),
+            ("node_wait_internal", &inner.node_wait_internal),
+        ] {
+            pending.push(QueuePending {
+                queue: name.to_string(),
+                queued: r.queued_actions().await,
+                waiting: r.waiting_actions().await,
+            });
+        }
+        let unmatched = self.unexpected_actions().await;
+        DivergenceReport::new(expected, pending, unmatched)
+    }
+
+    /// The actions the tested nodes performed, but the trace has not matched
+    pub async fn unexpected_actions(&self) -> Vec<ActionJson> {
+        let inner = &self.inner;
+        let mut vec = vec![];
+        for r in [
+            &inner.trace_wait_input_begin,
+            &inner.trace_wait_input_end,
+            &inner.trace_wait_output_begin,
+            &inner.trace_wait_output_end,
+            &inner.trace_wait_internal_begin,
+            &inner.trace_wait_internal_end,
+        ] {
+            vec.extend(r.queued_actions().await);
+        }
+        for r in [
+            &inner.node_wait_input,
+            &inner.node_wait_output,
+            &inner.node_wait_internal,
+        ] {
+            vec.extend(r.waiting_actions().await);
+        }
+        vec.extend(inner.node_timeout_actions.lock().await.iter().map(|a| { a.action.clone() }));
+
+        let mut unexpected: Vec<ActionJson> = vec![];
+        for a in vec {
+            if !unexpected.contains(&a) {
+                unexpected.push(a);
+            }
+        }
+        unexpected
+    }
+
     async fn expect_action_in_trace_gut(&self, action: &ActionJson, begin: bool) -> Res<bool> {
         trace!("enter, SIMULATOR: receive Action {:?}, {}", action, begin);
         let action_type = action.action_type()?;
+        let action = &self.project_state(action)?;
         match action_type {
-            ActionType::Input => {
+            ActionType::Setup | ActionType::Check | ActionType::Input => {
                 if begin {
                     if self.inner.wait_both_begin_and_end_action {
                         trace!("RECEIVE: notify case file {:?}", action);
@@ -207,8 +380,72 @@ impl ActionExecutor {
                     trace!("RECEIVE: notify case file {:?} done", action);
                 }
             }
+            ActionType::Fault => {
+                error!("a fault action is enacted by the player, not reported by nodes, {:?}", action);
+                return Err(ET::ParseError(format!(
+                    "fault action cannot be reported by node, it is enacted by the player, {:?}", action)));
+            }
         }
         trace!("exit, SIMULATOR: receive Action {:?}, {}", action, begin);
         Ok(true)
     }
-}
\ No newline at end of file
+
+    // a Check action with the projection of its state, which is matched with the projection of
+    // the state in the trace
+    fn project_state(&self, action: &ActionJson) -> Res<ActionJson> {
+        match &self.inner.state_projection {
+            Some(p) if action.action_type()? == ActionType::Check => {
+                action.with_payload(&p.project(&action.payload()?)?)
+            }
+            _ => { Ok(action.clone()) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use tokio::runtime::Builder;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use crate::action::action_json::ActionJson;
+    use crate::dtm::action_executor::ActionExecutor;
+    use crate::dtm::liveness::Liveness;
+    use crate::dtm::msg_ctrl::MessageControl;
+    use crate::dtm::trace_verdict::{TraceStep, TraceVerdict};
+
+    fn internal(op: u64) -> ActionJson {
+        ActionJson::from_value(json!({
+            "Internal": {"source": 1, "dest": 1, "payload": {"Op": op}}
+        }))
+    }
+
+    #[test]
+    fn test_timeout_verdict() {
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let executor = ActionExecutor::new(false, None, Liveness::new(1, false));
+            let step = TraceStep::new(1, None);
+
+            // the tested nodes performed nothing
+            let verdict = executor.timeout_verdict(0, step.clone(), internal(1)).await;
+            assert!(matches!(verdict, TraceVerdict::Timeout { .. }), "{}", verdict);
+
+            // a node requested an action not in the trace, which is not ACKed until released
+            let (sender, mut receiver) = unbounded_channel();
+            executor.expect_action_in_trace(
+                1, 0, "id_2".to_string(), internal(2), true, sender).await.unwrap();
+            assert!(receiver.try_recv().is_err());
+            let verdict = executor.timeout_verdict(0, step, internal(1)).await;
+            assert!(matches!(verdict, TraceVerdict::Diverged { .. }), "{}", verdict);
+
+            executor.release_timeout_actions().await;
+            let ack = receiver.try_recv().unwrap();
+            assert_eq!(ack.dest(), 1);
+            match ack.payload() {
+                MessageControl::ActionACK { id, .. } => { assert_eq!(id, "id_2"); }
+                m => { panic!("not an ACK {:?}", m); }
+            }
+        });
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+use tokio::select;
+use tokio::sync::Notify;
+use tracing::trace;
+
+use crate::action::action_json::ActionJson;
+use crate::dtm::liveness::Liveness;
+use crate::trace::trace_db::{RecordedAction, TraceDB};
+
+/// Record mode, in which the player acknowledges every begin and end action of the tested nodes
+/// at once, and appends them to the `recorded_action` table of a trace database in arrival order
+pub struct ActionRecorder {
+    db: Arc<TraceDB>,
+    run_id: String,
+    // the sequence number of the last action recorded
+    seq: Mutex<u64>,
+    recorded: Notify,
+}
+
+impl ActionRecorder {
+    pub fn new(db: Arc<TraceDB>, run_id: String) -> Self {
+        Self {
+            db,
+            run_id,
+            seq: Mutex::new(0),
+            recorded: Notify::new(),
+        }
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// The number of actions recorded
+    pub fn num_recorded(&self) -> u64 {
+        *self.seq.lock().unwrap()
+    }
+
+    /// Record an action reported by node `node_id`
+    pub fn record(&self, node_id: NID, action: &ActionJson, begin: bool) -> Res<()> {
+        let timestamp_micros = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| { d.as_micros() as u64 });
+        // hold the lock while writing, so the sequence numbers follow the arrival order
+        let mut seq = self.seq.lock().unwrap();
+        let recorded = RecordedAction {
+            run_id: self.run_id.clone(),
+            seq: *seq + 1,
+            timestamp_micros,
+            node_id,
+            begin,
+            action: action.serde_json_value_ref().clone(),
+        };
+        self.db.write_recorded_action(&recorded)?;
+        *seq += 1;
+        trace!("RECORD: No.{} from node {}, {:?}", *seq, node_id, action);
+        self.recorded.notify_waiters();
+        Ok(())
+    }
+
+    /// Wait until the tested nodes report no action before timeout
+    pub async fn wait_idle(&self, liveness: &Liveness) {
+        loop {
+            select! {
+                _ = self.recorded.notified() => {}
+                _ = liveness.wait_timeout() => {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::action::action_json::ActionJson;
+    use crate::dtm::action_recorder::ActionRecorder;
+    use crate::trace::trace_db::TraceDB;
+    use crate::util::tmp::tmp_dir;
+
+    #[test]
+    fn test_action_recorder() {
+        let path = tmp_dir(format!("record_{}.db", Uuid::new_v4()));
+        let db = Arc::new(TraceDB::new(path.clone()).unwrap());
+        let recorder = ActionRecorder::new(db.clone(), "run_1".to_string());
+        let action = ActionJson::from_value(json!({
+            "Internal": {"source": 2, "dest": 2, "payload": {"Tick": 1}}
+        }));
+        recorder.record(2, &action, true).unwrap();
+        recorder.record(2, &action, false).unwrap();
+        assert_eq!(recorder.num_recorded(), 2);
+
+        let recorded = db.read_recorded_action(recorder.run_id()).unwrap();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[0].seq, 1);
+        assert_eq!(recorded[0].node_id, 2);
+        assert_eq!(recorded[0].action, *action.serde_json_value_ref());
+        assert!(recorded[0].begin && !recorded[1].begin);
+        let _ = std::fs::remove_file(path);
+    }
+}
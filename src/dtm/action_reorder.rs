@@ -5,34 +5,40 @@ use scupt_util::res::Res;
 use scupt_util::serde_json_value::SerdeJsonValue;
 use tokio::select;
 use tokio::sync::Mutex;
-use tokio::time::Duration;
-use tokio::time::sleep;
 use tracing::{Instrument, trace, trace_span};
 
 use crate::action::action_json::ActionJson;
 use crate::dtm::action_channel::{ActionReceiver, ActionSender, io_event_channel};
+use crate::dtm::liveness::Liveness;
 
 type ActionCh = (ActionSender, ActionReceiver, Arc<Mutex<u64>>);
 
 #[derive(Clone)]
 pub struct ActionReorder {
-    seconds_timeout: u64,
+    liveness: Liveness,
     hash_map: Arc<Mutex<HashMap<ActionJson,
         ActionCh>>
     >,
+    // actions added but not received by any waiter yet
+    queued: Arc<Mutex<HashMap<ActionJson, u64>>>,
+    // actions being waited for but not added yet
+    waiting: Arc<Mutex<HashMap<ActionJson, u64>>>,
 }
 
 
 impl ActionReorder {
-    pub fn new(seconds_timeout: u64) -> Self {
+    pub fn new(liveness: Liveness) -> Self {
         Self {
-            seconds_timeout,
+            liveness,
             hash_map: Default::default(),
+            queued: Default::default(),
+            waiting: Default::default(),
         }
     }
 
     pub async fn wait_action(&self, action: &ActionJson) -> Res<bool> {
         let (_, r) = self.get_channel(action).await;
+        Self::inc_num(&self.waiting, action).await;
         let ok = select! {
             recv = r.recv() => {
                 self.dec_ref_num(action).await;
@@ -42,20 +48,68 @@ impl ActionReorder {
                 assert!(a2.eq(action));
                 true
             }
-            _ = sleep(Duration::from_secs(self.seconds_timeout)) => {
+            _ = self.liveness.wait_timeout() => {
                 false
             }
         };
+        Self::dec_num(&self.waiting, action).await;
+        if ok {
+            Self::dec_num(&self.queued, action).await;
+        }
         Ok(ok)
     }
 
     pub async fn add_action(&self, action: &ActionJson) -> Res<()> {
         let (s, _) = self.get_channel(action).await;
+        Self::inc_num(&self.queued, action).await;
         s.send(action.to_serde_json_string())?;
         self.dec_ref_num(action).await;
         Ok(())
     }
 
+    /// Actions added, but no one has received them
+    pub async fn queued_actions(&self) -> Vec<ActionJson> {
+        Self::actions(&self.queued).await
+    }
+
+    /// Actions being waited for, but no one has added them
+    pub async fn waiting_actions(&self) -> Vec<ActionJson> {
+        Self::actions(&self.waiting).await
+    }
+
+    async fn actions(map: &Mutex<HashMap<ActionJson, u64>>) -> Vec<ActionJson> {
+        let map = map.lock().await;
+        let mut vec = vec![];
+        for (a, n) in map.iter() {
+            for _ in 0..*n {
+                vec.push(a.clone());
+            }
+        }
+        vec
+    }
+
+    async fn inc_num(map: &Mutex<HashMap<ActionJson, u64>>, action: &ActionJson) {
+        let mut map = map.lock().await;
+        match map.get_mut(action) {
+            Some(n) => { *n += 1; }
+            None => { let _ = map.insert(action.clone(), 1); }
+        }
+    }
+
+    async fn dec_num(map: &Mutex<HashMap<ActionJson, u64>>, action: &ActionJson) {
+        let mut map = map.lock().await;
+        let remove = match map.get_mut(action) {
+            Some(n) => {
+                *n -= 1;
+                *n == 0
+            }
+            None => { false }
+        };
+        if remove {
+            let _ = map.remove(action);
+        }
+    }
+
     async fn get_channel(&self, action: &ActionJson) -> (ActionSender, ActionReceiver) {
         let mut map = self.hash_map.lock().await;
         let opt_recv = map.get(&action);
@@ -14,4 +14,16 @@ pub trait AsyncActionDriver: Sync + Send {
         dest_node_id: NID,
         action_json_str: String,
     ) -> Res<()>;
+
+    /// Ask the player for the value the trace picks for the choice `choice_kind`, among the
+    /// candidates in JSON, return the value in JSON, or None if the player picks no value
+    async fn choose(
+        &self,
+        choice_kind: String,
+        candidates_json_str: Vec<String>,
+    ) -> Res<Option<String>>;
+
+    /// The virtual time in milliseconds of the node, after waiting until it reaches `wait_until`,
+    /// return None if the player does not drive the virtual clock
+    async fn clock(&self, wait_until: Option<u64>) -> Res<Option<u64>>;
 }
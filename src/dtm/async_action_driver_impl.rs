@@ -52,6 +52,23 @@ impl AsyncActionDriver for AsyncActionDriverImpl {
             }
         }
     }
+
+    async fn choose(&self, choice_kind: String, candidates_json_str: Vec<String>) -> Res<Option<String>> {
+        let candidates = candidates_json_str.into_iter().map(SerdeJsonString::new).collect();
+        self.inner.async_choose(choice_kind, candidates).await
+    }
+
+    async fn clock(&self, wait_until: Option<u64>) -> Res<Option<u64>> {
+        let m = MessageControl::ClockReq {
+            id: Uuid::new_v4().to_string(),
+            wait_until,
+        };
+        let response = self.inner.async_request(m).await?;
+        match response {
+            MessageControl::ClockResp { now, .. } => { Ok(now) }
+            _ => { panic!("not possible") }
+        }
+    }
 }
 
 impl AsyncActionDriverImplInner {
@@ -78,6 +95,31 @@ impl AsyncActionDriverImplInner {
             action,
             begin: begin_action,
         };
+        let response = self.async_request(m).await?;
+        match response {
+            MessageControl::ActionACK { .. } => {}
+            _ => { panic!("not possible") }
+        }
+        Ok(())
+    }
+
+    async fn async_choose(&self, kind: String, candidates: Vec<SerdeJsonString>) -> Res<Option<String>> {
+        let uuid = Uuid::new_v4();
+        let m = MessageControl::ChooseReq {
+            id: uuid.to_string(),
+            kind,
+            candidates,
+        };
+        let response = self.async_request(m).await?;
+        match response {
+            MessageControl::ChooseResp { value, .. } => {
+                Ok(value.map(|v| { v.to_string() }))
+            }
+            _ => { panic!("not possible") }
+        }
+    }
+
+    async fn async_request(&self, m: MessageControl) -> Res<MessageControl> {
         let req = Message::new(m, self.node_id, self.server_id);
 
         // send request
@@ -94,11 +136,7 @@ impl AsyncActionDriverImplInner {
         };
 
         trace!("receive response {:?}", response);
-        match response {
-            MessageControl::ActionACK { .. } => {}
-            _ => { panic!("not possible") }
-        }
-        Ok(())
+        Ok(response)
     }
 }
 
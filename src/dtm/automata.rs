@@ -2,6 +2,7 @@ use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use lazy_static::lazy_static;
 use scc::HashIndex;
@@ -16,6 +17,7 @@ use scupt_util::node_id::NID;
 use scupt_util::res::Res;
 use scupt_util::res_of::res_io;
 use scupt_util::serde_json_string::SerdeJsonString;
+use serde_json::Value;
 use tokio::runtime::Builder;
 use tracing::{debug, error};
 
@@ -23,6 +25,8 @@ use crate::action::action_type::{ActionBeginEnd, ActionType};
 use crate::action::message_json::MessageJson;
 use crate::dtm::async_action_driver::AsyncActionDriver;
 use crate::dtm::dtm_client::DTMClient;
+use crate::dtm::dtm_in_process::DTMInProcess;
+use crate::dtm::fault::{FaultKind, MessageFaults};
 use crate::dtm::sync_action_driver::SyncActionDriver;
 
 /// Initialize an automata setting
@@ -46,6 +50,23 @@ pub fn automata_setup_with_input(
     action_driver_setup_gut(name, tested_nid, Some(tested_addr), player_nid, player_addr);
 }
 
+/// Initialize an automata setting, whose tested node reports actions to a player running in the
+/// same process
+pub fn automata_setup_in_process(
+    name: &str,
+    tested_nid: NID,
+    player: &DTMInProcess,
+) {
+    if !__DRIVERS.contains(name) {
+        let driver = __ActionDriver::new_in_process(tested_nid, player).unwrap();
+        let opt_d = __DRIVERS.insert(name.to_string(), driver);
+        debug!("create in process driver tested node:{}", tested_nid);
+        if let Err((k, _)) = opt_d {
+            panic!("existing key {}", k);
+        }
+    }
+}
+
 /// Clean an automata setting
 pub fn automata_clear(name: &str) {
     action_driver_unset_gut(name)
@@ -62,6 +83,32 @@ pub fn automata_enable(name: &str) -> bool {
     __DRIVERS.contains(name)
 }
 
+/// Report to the player that the tested node is paused or resumed, the player does not count
+/// timeout while the node is paused
+pub fn automata_pause(name: &str, paused: bool) {
+    let opt = __DRIVERS.get(&name.to_string());
+    match opt {
+        Some(t) => {
+            if let Some(c) = &t.get()._dtm_client {
+                c.set_paused(paused);
+            }
+        }
+        None => {}
+    }
+}
+
+/// The fault the player injects on the next message the tested node sends to `dest`.
+/// The node drops the message when it is `Drop`, sends it twice when it is `Duplicate`, and
+/// sends it as usual when it is None.
+pub fn automata_message_fault(name: &str, dest: NID) -> Option<FaultKind> {
+    let opt = __DRIVERS.get(&name.to_string());
+    match opt {
+        Some(t) => {
+            t.get()._faults.next_fault(dest)
+        }
+        None => { None }
+    }
+}
 
 /// Automata read next input action
 pub fn automata_next_input(
@@ -135,6 +182,117 @@ pub fn automata_send_action_to_player(
 }
 
 
+/// The value the player picks for the nondeterministic choice `choice_kind` of the tested node,
+/// such as a random election timeout, among `candidates`, or any value if `candidates` is empty.
+/// The value is in the payload of an Internal action of the trace, see `automata_choose_json`.
+/// Return None when the automata is not enabled or the player picks no value, then the node
+/// makes the choice by itself.
+pub fn automata_choose(
+    automata_name: &str,
+    choice_kind: &str,
+    candidates: &[Value],
+) -> Option<Value> {
+    let candidates = candidates.iter().map(|v| { v.to_string() }).collect();
+    let opt = choose_gut(automata_name, choice_kind, candidates)?;
+    serde_json::from_str(opt.as_str()).ok()
+}
+
+/// The same as `automata_choose`, the candidates are a JSON array, and the value returned is in
+/// JSON.
+/// The player waits for an Internal action of the tested node in the trace, whose payload has the
+/// key `choice_kind`, and picks the value of the key, for example, `150` in
+/// `{"Raft":{"ElectionTimeout":150}}` for the choice `ElectionTimeout`.
+pub fn automata_choose_json(
+    automata_name: &str,
+    choice_kind: &str,
+    candidates_json: &str,
+) -> Option<String> {
+    let candidates: Vec<Value> = match serde_json::from_str(candidates_json) {
+        Ok(v) => { v }
+        Err(e) => {
+            error!("candidates of choice {} is not a JSON array, {}, error: {}", choice_kind, candidates_json, e);
+            return None;
+        }
+    };
+    let candidates = candidates.iter().map(|v| { v.to_string() }).collect();
+    choose_gut(automata_name, choice_kind, candidates)
+}
+
+/// A-synchronize version of `automata_choose`
+pub async fn automata_choose_async(
+    automata_name: &str,
+    choice_kind: &str,
+    candidates: &[Value],
+) -> Option<Value> {
+    let opt = __DRIVERS.get_async(&automata_name.to_string()).await;
+    let driver = match opt {
+        Some(e) => { e.get()._driver_async.clone() }
+        None => {
+            return None;
+        }
+    };
+    let candidates = candidates.iter().map(|v| { v.to_string() }).collect();
+    let r = driver.choose(choice_kind.to_string(), candidates).await;
+    let opt = choose_result(choice_kind, r)?;
+    serde_json::from_str(opt.as_str()).ok()
+}
+
+/// The time in milliseconds of the tested node.
+/// Under test, it is the virtual time, which starts at 0 and advances only when the player
+/// reaches a Tick action of the node in the trace, otherwise, it is the real time since the UNIX
+/// epoch.
+/// It blocks the calling thread, `automata_now_async` is for a task of an async runtime.
+pub fn automata_now(automata_name: &str) -> u64 {
+    let opt = __DRIVERS.get(&automata_name.to_string());
+    let opt_now = match opt {
+        Some(e) => {
+            let r = e.get()._driver_sync.clock(None);
+            clock_result(r)
+        }
+        None => { None }
+    };
+    opt_now.unwrap_or_else(real_clock_now)
+}
+
+/// A-synchronize version of `automata_now`
+pub async fn automata_now_async(automata_name: &str) -> u64 {
+    clock_async_gut(automata_name, None).await
+}
+
+/// Sleep `duration` by the clock of `automata_now`, under test, it returns after the trace
+/// advances the virtual clock enough
+pub async fn automata_sleep(automata_name: &str, duration: Duration) {
+    let now = clock_async_gut(automata_name, None).await;
+    let _ = clock_async_gut(automata_name, Some(now + duration.as_millis() as u64)).await;
+}
+
+/// A timer which ticks every `period` by the clock of `automata_now`
+pub fn automata_timer(automata_name: &str, period: Duration) -> AutomataTimer {
+    AutomataTimer {
+        automata_name: automata_name.to_string(),
+        period_millis: period.as_millis() as u64,
+        next_tick: None,
+    }
+}
+
+/// A timer created by `automata_timer`
+pub struct AutomataTimer {
+    automata_name: String,
+    period_millis: u64,
+    next_tick: Option<u64>,
+}
+
+impl AutomataTimer {
+    /// Wait for the next tick and return the time of it, the first tick completes immediately,
+    /// like `tokio::time::Interval::tick`
+    pub async fn tick(&mut self) -> u64 {
+        let now = clock_async_gut(self.automata_name.as_str(), self.next_tick).await;
+        let tick = self.next_tick.unwrap_or(now);
+        self.next_tick = Some(tick + self.period_millis);
+        now
+    }
+}
+
 /// A-synchronize begin an action
 pub async fn automata_action_async<M: MsgTrait + 'static>(
     automata_name: &str,
@@ -161,6 +319,21 @@ macro_rules! auto_init {
     };
 }
 
+/// Initialize an automata, whose tested node reports actions to the in-process player `player`,
+/// which is a `DTMInProcess`
+#[macro_export]
+macro_rules! auto_init_in_process {
+    (
+        $automata_name:expr,
+        $node_id:expr,
+        $player:expr
+    ) => {
+        {
+            $crate::dtm::automata::automata_setup_in_process($automata_name, $node_id, $player);
+        }
+    };
+}
+
 /// Clear an automata setting
 #[macro_export]
 macro_rules! auto_clear {
@@ -257,6 +430,34 @@ macro_rules! setup_end {
     };
 }
 
+/// Begin a Check action
+#[macro_export]
+macro_rules! check_begin {
+    ($automata_name:expr,  $message:expr) => {
+        {
+            $crate::action_begin!(
+                $automata_name,
+                $crate::action::action_type::ActionType::Check,
+                $message
+            )
+        }
+    };
+}
+
+/// End a Check action, the payload of `$message` is the state the node observes
+#[macro_export]
+macro_rules! check_end {
+    ($automata_name:expr,  $message:expr) => {
+        {
+            $crate::action_end!(
+                $automata_name,
+                $crate::action::action_type::ActionType::Check,
+                $message
+            )
+        }
+    };
+}
+
 /// Begin an Input action
 #[macro_export]
 macro_rules! input_begin {
@@ -376,6 +577,73 @@ fn action_driver_close_input(name: &str) {
     }
 }
 
+fn choose_gut(
+    automata_name: &str,
+    choice_kind: &str,
+    candidates: Vec<String>,
+) -> Option<String> {
+    let opt = __DRIVERS.get(&automata_name.to_string());
+    let driver = match opt {
+        Some(e) => { e.get()._driver_sync.clone() }
+        None => {
+            return None;
+        }
+    };
+    let r = driver.choose(choice_kind.to_string(), candidates);
+    choose_result(choice_kind, r)
+}
+
+fn choose_result(choice_kind: &str, r: Res<Option<String>>) -> Option<String> {
+    match r {
+        Ok(opt) => { opt }
+        Err(e) => {
+            error!("choose {}, error: {}.", choice_kind, e);
+            None
+        }
+    }
+}
+
+async fn clock_async_gut(automata_name: &str, wait_until: Option<u64>) -> u64 {
+    let opt = __DRIVERS.get_async(&automata_name.to_string()).await;
+    let opt_now = match opt {
+        Some(e) => {
+            let driver = e.get()._driver_async.clone();
+            clock_result(driver.clock(wait_until).await)
+        }
+        None => { None }
+    };
+    match opt_now {
+        Some(now) => { now }
+        None => {
+            // the player does not drive the clock
+            if let Some(deadline) = wait_until {
+                let now = real_clock_now();
+                if deadline > now {
+                    tokio::time::sleep(Duration::from_millis(deadline - now)).await;
+                }
+            }
+            real_clock_now()
+        }
+    }
+}
+
+fn clock_result(r: Res<Option<u64>>) -> Option<u64> {
+    match r {
+        Ok(opt) => { opt }
+        Err(e) => {
+            error!("virtual clock, error: {}.", e);
+            None
+        }
+    }
+}
+
+fn real_clock_now() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => { d.as_millis() as u64 }
+        Err(_) => { 0 }
+    }
+}
+
 fn action_driver_setup_gut(
     name: &str,
     tested_nid: NID,
@@ -436,7 +704,9 @@ unsafe impl Send for __ActionDriver {}
 #[derive(Clone)]
 struct __ActionDriver {
     _thd: Arc<Mutex<Vec<JoinHandle<()>>>>,
-    _dtm_client: Arc<DTMClient>,
+    // None when the player runs in the same process
+    _dtm_client: Option<Arc<DTMClient>>,
+    _faults: Arc<MessageFaults>,
     _driver_async: Arc<dyn AsyncActionDriver>,
     _driver_sync: Arc<dyn SyncActionDriver>,
     _client_stop: Notifier,
@@ -520,7 +790,8 @@ impl __ActionDriver {
         };
         let s = Self {
             _thd: Arc::new(Mutex::new(vec_thd)),
-            _dtm_client: Arc::new(cli),
+            _faults: cli.message_faults(),
+            _dtm_client: Some(Arc::new(cli)),
             _driver_async: async_driver,
             _driver_sync: sync_driver,
             _client_stop: client_stop_notifier,
@@ -530,10 +801,26 @@ impl __ActionDriver {
         Ok(s)
     }
 
+    fn new_in_process(tested_nid: NID, player: &DTMInProcess) -> Res<Self> {
+        let node_name = format!("driver_{}_in_process", tested_nid);
+        Ok(Self {
+            _thd: Default::default(),
+            _dtm_client: None,
+            _faults: player.message_faults(tested_nid),
+            _driver_async: player.new_async_driver(tested_nid)?,
+            _driver_sync: player.new_sync_driver(tested_nid)?,
+            _client_stop: Notifier::new_with_name(node_name.clone() + "_client"),
+            _server_stop: Notifier::new_with_name(node_name + "_server"),
+            _server: Default::default(),
+        })
+    }
+
     fn close(&self) {
         self._client_stop.notify_all();
         self._server_stop.notify_all();
-        self._dtm_client.close();
+        if let Some(c) = &self._dtm_client {
+            c.close();
+        }
     }
 
     fn close_incoming(&self) {
@@ -0,0 +1,131 @@
+use std::sync::Mutex;
+
+use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+use serde_json::Value;
+use tokio::select;
+use tokio::sync::Notify;
+
+use crate::action::action_json::ActionJson;
+use crate::action::action_type::ActionType;
+use crate::dtm::liveness::Liveness;
+
+/// The nondeterministic choices offered by the trace.
+///
+/// An Internal action is offered while the player waits for a node to perform it, the value of a
+/// choice is the value of the key named by the choice kind in the payload of the action.
+/// For example, the action with payload `{"Raft":{"ElectionTimeout":150}}` picks `150` for the
+/// choice `ElectionTimeout`.
+pub struct ChoiceOffers {
+    liveness: Liveness,
+    offers: Mutex<Vec<ActionJson>>,
+    notify: Notify,
+}
+
+impl ChoiceOffers {
+    pub fn new(liveness: Liveness) -> Self {
+        Self {
+            liveness,
+            offers: Default::default(),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn offer(&self, action: &ActionJson) {
+        self.offers.lock().unwrap().push(action.clone());
+        self.notify.notify_waiters();
+    }
+
+    /// Withdraw an offer, which has not been chosen by the node
+    pub fn withdraw(&self, action: &ActionJson) {
+        let mut offers = self.offers.lock().unwrap();
+        if let Some(i) = offers.iter().position(|a| { a == action }) {
+            let _ = offers.remove(i);
+        }
+    }
+
+    /// Wait for an action of node `node_id` which picks a value among `candidates` for the
+    /// choice `kind`, any value is allowed when `candidates` is empty.
+    /// Return None when timeout.
+    pub async fn choose(&self, node_id: NID, kind: &str, candidates: &[Value]) -> Res<Option<Value>> {
+        let timeout = self.liveness.wait_timeout();
+        tokio::pin!(timeout);
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            // register before taking, so an offer in between is not missed
+            notified.as_mut().enable();
+            if let Some(v) = self.take(node_id, kind, candidates)? {
+                return Ok(Some(v));
+            }
+            select! {
+                _ = &mut notified => {}
+                _ = &mut timeout => {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn take(&self, node_id: NID, kind: &str, candidates: &[Value]) -> Res<Option<Value>> {
+        let mut offers = self.offers.lock().unwrap();
+        for i in 0..offers.len() {
+            let a = &offers[i];
+            if a.action_type()? != ActionType::Internal || a.dest_nid()? != node_id {
+                continue;
+            }
+            if let Some(v) = choice_value(&a.payload()?, kind) {
+                if candidates.is_empty() || candidates.contains(&v) {
+                    let _ = offers.remove(i);
+                    return Ok(Some(v));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The value of key `kind` in `payload`, the objects are searched depth first
+pub fn choice_value(payload: &Value, kind: &str) -> Option<Value> {
+    match payload {
+        Value::Object(map) => {
+            if let Some(v) = map.get(kind) {
+                return Some(v.clone());
+            }
+            map.values().find_map(|v| { choice_value(v, kind) })
+        }
+        _ => { None }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::action::action_json::ActionJson;
+    use crate::dtm::choice::{choice_value, ChoiceOffers};
+    use crate::dtm::liveness::Liveness;
+
+    #[test]
+    fn test_choice() {
+        let payload = json!({"Raft": {"ElectionTimeout": 150}});
+        assert_eq!(choice_value(&payload, "ElectionTimeout"), Some(json!(150)));
+        assert_eq!(choice_value(&payload, "Peer"), None);
+
+        let action = ActionJson::from_value(json!({
+            "Internal": {"source": 1, "dest": 1, "payload": payload}
+        }));
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let offers = ChoiceOffers::new(Liveness::new(1, false));
+            offers.offer(&action);
+            // another node, or a value not in the candidates
+            assert_eq!(offers.choose(2, "ElectionTimeout", &[]).await.unwrap(), None);
+            assert_eq!(offers.choose(1, "ElectionTimeout", &[json!(300)]).await.unwrap(), None);
+            let v = offers.choose(1, "ElectionTimeout", &[json!(150), json!(300)]).await.unwrap();
+            assert_eq!(v, Some(json!(150)));
+            // an offer is chosen at most once
+            assert_eq!(offers.choose(1, "ElectionTimeout", &[]).await.unwrap(), None);
+        });
+    }
+}
@@ -0,0 +1,234 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::action::action_json::ActionJson;
+
+/// The maximum number of the closest candidates kept in a report
+const MAX_CLOSEST_CANDIDATES: usize = 3;
+
+/// The actions pending on a reorder queue of the player
+#[derive(Clone, Debug)]
+pub struct QueuePending {
+    /// the name of the queue, such as `trace_wait_output_begin`
+    pub queue: String,
+
+    /// actions added to the queue, which no one has received
+    pub queued: Vec<ActionJson>,
+
+    /// actions being waited for on the queue, which no one has added
+    pub waiting: Vec<ActionJson>,
+}
+
+/// A field of the expected action which differs from the actual one
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldDiff {
+    /// JSON pointer of the field, such as `/Output/payload/term`
+    pub path: String,
+
+    /// None when the field does not exist in the expected action
+    pub expected: Option<Value>,
+
+    /// None when the field does not exist in the actual action
+    pub actual: Option<Value>,
+}
+
+/// An unmatched action and its difference from the expected action
+#[derive(Clone, Debug)]
+pub struct CandidateDiff {
+    pub action: ActionJson,
+    pub diff: Vec<FieldDiff>,
+}
+
+/// What the tested nodes actually did, versus what the trace expected, when a step fails
+#[derive(Clone, Debug)]
+pub struct DivergenceReport {
+    /// the action the trace expected at the failing step
+    pub expected: ActionJson,
+
+    /// the non-empty queues of the player
+    pub pending: Vec<QueuePending>,
+
+    /// the actions the tested nodes performed, which no trace step has matched
+    pub unmatched: Vec<ActionJson>,
+
+    /// the unmatched actions closest to the expected action, the closest one first
+    pub closest: Vec<CandidateDiff>,
+}
+
+impl DivergenceReport {
+    pub fn new(
+        expected: ActionJson,
+        pending: Vec<QueuePending>,
+        unmatched: Vec<ActionJson>,
+    ) -> Self {
+        let mut candidates: Vec<(usize, CandidateDiff)> = unmatched.iter().map(|a| {
+            let diff = json_diff(expected.serde_json_value_ref(), a.serde_json_value_ref());
+            let distance = diff.iter().map(|d| { d.distance() }).sum();
+            (distance, CandidateDiff { action: a.clone(), diff })
+        }).collect();
+        candidates.sort_by_key(|(distance, _)| { *distance });
+        let closest = candidates.into_iter()
+            .take(MAX_CLOSEST_CANDIDATES)
+            .map(|(_, c)| { c })
+            .collect();
+        Self {
+            expected,
+            pending: pending.into_iter()
+                .filter(|p| { !p.queued.is_empty() || !p.waiting.is_empty() })
+                .collect(),
+            unmatched,
+            closest,
+        }
+    }
+
+    /// true if the tested nodes performed any action not matched by the trace
+    pub fn has_unmatched(&self) -> bool {
+        !self.unmatched.is_empty()
+    }
+}
+
+impl FieldDiff {
+    /// the number of leaf values differ
+    fn distance(&self) -> usize {
+        let n1 = self.expected.as_ref().map_or(0, num_leaf);
+        let n2 = self.actual.as_ref().map_or(0, num_leaf);
+        n1.max(n2).max(1)
+    }
+}
+
+fn num_leaf(value: &Value) -> usize {
+    match value {
+        Value::Object(map) => { map.values().map(num_leaf).sum() }
+        Value::Array(vec) => { vec.iter().map(num_leaf).sum() }
+        _ => { 1 }
+    }
+}
+
+/// The field-level difference between two JSON values
+pub fn json_diff(expected: &Value, actual: &Value) -> Vec<FieldDiff> {
+    let mut vec = vec![];
+    json_diff_gut("", expected, actual, &mut vec);
+    vec
+}
+
+fn json_diff_gut(path: &str, expected: &Value, actual: &Value, diff: &mut Vec<FieldDiff>) {
+    match (expected, actual) {
+        (Value::Object(m1), Value::Object(m2)) => {
+            for (k, v1) in m1.iter() {
+                let p = format!("{}/{}", path, k);
+                match m2.get(k) {
+                    Some(v2) => { json_diff_gut(p.as_str(), v1, v2, diff); }
+                    None => {
+                        diff.push(FieldDiff { path: p, expected: Some(v1.clone()), actual: None });
+                    }
+                }
+            }
+            for (k, v2) in m2.iter() {
+                if !m1.contains_key(k) {
+                    let p = format!("{}/{}", path, k);
+                    diff.push(FieldDiff { path: p, expected: None, actual: Some(v2.clone()) });
+                }
+            }
+        }
+        (Value::Array(a1), Value::Array(a2)) => {
+            for i in 0..a1.len().max(a2.len()) {
+                let p = format!("{}/{}", path, i);
+                match (a1.get(i), a2.get(i)) {
+                    (Some(v1), Some(v2)) => { json_diff_gut(p.as_str(), v1, v2, diff); }
+                    (v1, v2) => {
+                        diff.push(FieldDiff { path: p, expected: v1.cloned(), actual: v2.cloned() });
+                    }
+                }
+            }
+        }
+        (v1, v2) => {
+            if v1 != v2 {
+                diff.push(FieldDiff {
+                    path: path.to_string(),
+                    expected: Some(v1.clone()),
+                    actual: Some(v2.clone()),
+                });
+            }
+        }
+    }
+}
+
+fn opt_value_to_string(opt: &Option<Value>) -> String {
+    match opt {
+        Some(v) => { v.to_string() }
+        None => { "<none>".to_string() }
+    }
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: expected {}, actual {}",
+               self.path,
+               opt_value_to_string(&self.expected),
+               opt_value_to_string(&self.actual))
+    }
+}
+
+impl fmt::Display for DivergenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "expected action: {}", self.expected.serde_json_value_ref())?;
+        if !self.pending.is_empty() {
+            writeln!(f, "pending actions:")?;
+            for p in self.pending.iter() {
+                for a in p.queued.iter() {
+                    writeln!(f, "  {} queued: {}", p.queue, a.serde_json_value_ref())?;
+                }
+                for a in p.waiting.iter() {
+                    writeln!(f, "  {} waiting: {}", p.queue, a.serde_json_value_ref())?;
+                }
+            }
+        }
+        if !self.unmatched.is_empty() {
+            writeln!(f, "unmatched actions of the tested nodes:")?;
+            for a in self.unmatched.iter() {
+                writeln!(f, "  {}", a.serde_json_value_ref())?;
+            }
+        }
+        for c in self.closest.iter() {
+            writeln!(f, "closest candidate: {}", c.action.serde_json_value_ref())?;
+            for d in c.diff.iter() {
+                writeln!(f, "  {}", d)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::action::action_json::ActionJson;
+    use crate::dtm::divergence_report::{DivergenceReport, json_diff};
+
+    #[test]
+    fn test_divergence_report() {
+        let expected = ActionJson::from_value(json!({
+            "Output": {"source": 1, "dest": 2, "payload": {"Vote": {"term": 2, "granted": true}}}
+        }));
+        let other_type = ActionJson::from_value(json!({
+            "Input": {"source": 2, "dest": 1, "payload": {"Append": {"term": 2, "entries": [1, 2]}}}
+        }));
+        let one_field = ActionJson::from_value(json!({
+            "Output": {"source": 1, "dest": 2, "payload": {"Vote": {"term": 3, "granted": true}}}
+        }));
+
+        let diff = json_diff(expected.serde_json_value_ref(), one_field.serde_json_value_ref());
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].path, "/Output/payload/Vote/term");
+        assert_eq!(diff[0].expected, Some(json!(2)));
+        assert_eq!(diff[0].actual, Some(json!(3)));
+
+        let report = DivergenceReport::new(
+            expected, vec![], vec![other_type, one_field.clone()]);
+        assert!(report.has_unmatched());
+        assert_eq!(report.closest[0].action, one_field);
+        assert!(report.to_string().contains("/Output/payload/Vote/term: expected 2, actual 3"));
+    }
+}
@@ -1,6 +1,7 @@
 use std::fmt::Debug;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender as SyncSender;
 use std::sync::Mutex as StdMutex;
 use std::time::Duration;
@@ -23,10 +24,12 @@ use tokio::sync::mpsc::UnboundedReceiver as AsyncReceiver;
 use tokio::sync::mpsc::UnboundedSender as AsyncSender;
 use tokio::sync::oneshot::Sender as AsyncOneshotSender;
 use tokio::task::LocalSet;
-use tokio::time::sleep;
+use tokio::time::{Interval, interval, sleep};
 
 use crate::dtm::async_action_driver::AsyncActionDriver;
 use crate::dtm::async_action_driver_impl::AsyncActionDriverImpl;
+use crate::dtm::fault::{FaultKind, MessageFaults};
+use crate::dtm::liveness::HEARTBEAT_INTERVAL_MILLIS;
 use crate::dtm::msg_ctrl::MessageControl;
 use crate::dtm::sync_action_driver::SyncActionDriver;
 use crate::dtm::sync_action_driver_impl::SyncActionDriverImpl;
@@ -47,6 +50,10 @@ struct _ClientContext {
     async_receiver: StdMutex<Option<AsyncReceiver<(Message<MessageControl>, AsyncOneshotSender<Message<MessageControl>>)>>>,
     sync_sender: AsyncSender<(Message<MessageControl>, SyncSender<Message<MessageControl>>)>,
     sync_receiver: StdMutex<Option<AsyncReceiver<(Message<MessageControl>, SyncSender<Message<MessageControl>>)>>>,
+    // reported to the player by heartbeat, the player does not count timeout when paused
+    paused: AtomicBool,
+    // the message faults delivered by the player, which are enacted by the tested node
+    faults: Arc<MessageFaults>,
 }
 
 #[derive(Clone)]
@@ -85,6 +92,8 @@ impl DTMClient {
                 async_receiver: StdMutex::new(Some(async_receiver)),
                 sync_sender,
                 sync_receiver: StdMutex::new(Some(sync_receiver)),
+                paused: AtomicBool::new(false),
+                faults: Default::default(),
             }),
         })
     }
@@ -139,6 +148,22 @@ impl DTMClient {
     pub fn close(&self) {
         self.context.close();
     }
+
+    /// Report to the player that the tested node is paused or resumed
+    pub fn set_paused(&self, paused: bool) {
+        self.context.paused.store(paused, Ordering::SeqCst);
+    }
+
+    /// The fault on the next message the tested node sends to `dest`, see
+    /// `MessageFaults::next_fault`
+    pub fn message_fault(&self, dest: NID) -> Option<FaultKind> {
+        self.context.faults.next_fault(dest)
+    }
+
+    /// The message faults delivered by the player to this tested node
+    pub fn message_faults(&self) -> Arc<MessageFaults> {
+        self.context.faults.clone()
+    }
 }
 
 impl _ClientContext {
@@ -178,11 +203,13 @@ impl _ClientContext {
         let endpoint2 = self.connect_to_dtm_player().await?;
         let resp_senders1 = HashMap::new();
         let resp_senders2 = HashMap::new();
+        let mut heartbeat = interval(Duration::from_millis(HEARTBEAT_INTERVAL_MILLIS));
 
         loop {
             self.handle_message(
                 &*endpoint1, &mut receiver1, &resp_senders1,
-                &*endpoint2, &mut receiver2, &resp_senders2).await?;
+                &*endpoint2, &mut receiver2, &resp_senders2,
+                &mut heartbeat).await?;
         }
     }
 
@@ -195,23 +222,37 @@ impl _ClientContext {
         endpoint2: &dyn EndpointAsync<MessageControl>,
         incoming2: &mut AsyncReceiver<(Message<MessageControl>, SyncSender<Message<MessageControl>>)>,
         resp_senders2: &HashMap<String, SyncSender<Message<MessageControl>>>,
+        heartbeat: &mut Interval,
     ) -> Res<()> {
+        // a request is sent in the branch body, which is not cancelled by the other branches
         select! {
             r1 = self.handle_recv_response_async(endpoint1, resp_senders1) => {
                 r1
             },
-            r2 = self.handle_incoming_request(endpoint1, incoming1, resp_senders1) => {
-                r2
+            opt_in = incoming1.recv() => {
+                self.handle_incoming_request(endpoint1, opt_in, resp_senders1).await
             }
             r3 = self.handle_recv_response_sync(endpoint2, resp_senders2) => {
                 r3
             },
-            r4 = self.handle_incoming_request(endpoint2, incoming2, resp_senders2) => {
-                r4
+            opt_in = incoming2.recv() => {
+                self.handle_incoming_request(endpoint2, opt_in, resp_senders2).await
+            }
+            _ = heartbeat.tick() => {
+                self.send_heartbeat(endpoint1).await
             }
         }
     }
 
+    async fn send_heartbeat(&self, endpoint: &dyn EndpointAsync<MessageControl>) -> Res<()> {
+        let m = Message::new(
+            MessageControl::Heartbeat { paused: self.paused.load(Ordering::SeqCst) },
+            self.node_id,
+            self.dtm_server_node_id,
+        );
+        endpoint.send(m).await
+    }
+
     async fn handle_recv_response<S>(
         &self,
         endpoint: &dyn EndpointAsync<MessageControl>,
@@ -225,6 +266,9 @@ impl _ClientContext {
             Err(e) => { return Err(e); }
         };
 
+        if let MessageControl::ActionACK { faults, .. } = &m {
+            self.faults.add(faults);
+        }
         let id = m.id().unwrap();
         let opt = resp_senders.remove(&id);
         let sender = match opt {
@@ -265,10 +309,9 @@ impl _ClientContext {
     async fn handle_incoming_request<S: Debug>(
         &self,
         endpoint: &dyn EndpointAsync<MessageControl>,
-        incoming: &mut AsyncReceiver<(Message<MessageControl>, S)>,
+        opt_in: Option<(Message<MessageControl>, S)>,
         resp_senders: &HashMap<String, S>,
     ) -> Res<()> {
-        let opt_in = incoming.recv().await;
         match opt_in {
             Some((m, s)) => {
                 let id = m.payload_ref().id().unwrap();
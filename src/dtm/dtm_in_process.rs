@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::sync::mpsc::Sender as SyncSender;
+
+use async_trait::async_trait;
+use scupt_net::notifier::Notifier;
+use scupt_net::task::spawn_local_task;
+use scupt_util::error_type::ET;
+use scupt_util::message::{Message, MsgTrait};
+use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+use scupt_util::res_of::res_option;
+use scupt_util::serde_json_string::SerdeJsonString;
+use tokio::select;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::oneshot;
+use tokio::sync::oneshot::Sender as AsyncOneshotSender;
+use tokio::task::LocalSet;
+
+use crate::action::res_serde::res_serde;
+use crate::dtm::action_incoming::ActionIncoming;
+use crate::dtm::async_action_driver::AsyncActionDriver;
+use crate::dtm::async_action_driver_impl::AsyncActionDriverImpl;
+use crate::dtm::dtm_player::TestOption;
+use crate::dtm::dtm_server_handler::DTMServerHandler;
+use crate::dtm::fault::MessageFaults;
+use crate::dtm::message_to_node::MessageToNode;
+use crate::dtm::msg_ctrl::MessageControl;
+use crate::dtm::sync_action_driver::SyncActionDriver;
+use crate::dtm::sync_action_driver_impl::SyncActionDriverImpl;
+use crate::dtm::trace_verdict::TraceVerdict;
+
+type AsyncRequest = (Message<MessageControl>, AsyncOneshotSender<Message<MessageControl>>);
+
+type SyncRequest = (Message<MessageControl>, SyncSender<Message<MessageControl>>);
+
+/// Deliver the messages to the tested nodes by the channels of their inboxes
+#[derive(Default)]
+struct MessageToNodeChannel {
+    nodes: StdMutex<HashMap<NID, UnboundedSender<Message<SerdeJsonString>>>>,
+}
+
+/// The messages of the Input actions, which the player delivers to a tested node
+pub struct NodeInbox<M: MsgTrait + 'static> {
+    receiver: UnboundedReceiver<Message<SerdeJsonString>>,
+    _phantom: PhantomData<M>,
+}
+
+struct _InProcessContext {
+    node_id: NID,
+    handler: DTMServerHandler,
+    notify: Notifier,
+    nodes: Arc<MessageToNodeChannel>,
+    faults: StdMutex<HashMap<NID, Arc<MessageFaults>>>,
+    async_sender: UnboundedSender<AsyncRequest>,
+    async_receiver: StdMutex<Option<UnboundedReceiver<AsyncRequest>>>,
+    sync_sender: UnboundedSender<SyncRequest>,
+    sync_receiver: StdMutex<Option<UnboundedReceiver<SyncRequest>>>,
+}
+
+/// A deterministic testing player running in the same process as the tested nodes.
+///
+/// The tested nodes report actions by the same action drivers as a `DTMClient` does, and receive
+/// the messages of Input actions from a `NodeInbox`, all over channels rather than TCP sockets.
+/// So a whole system can be driven by a trace inside one tokio runtime, for example, in a
+/// `#[test]`.
+#[derive(Clone)]
+pub struct DTMInProcess {
+    context: Arc<_InProcessContext>,
+}
+
+impl DTMInProcess {
+    pub fn new(node_id: NID, stop_notify: Notifier, option: TestOption) -> Self {
+        let nodes = Arc::new(MessageToNodeChannel::default());
+        let handler = DTMServerHandler::new(node_id, nodes.clone(), stop_notify.clone(), option);
+        let (async_sender, async_receiver) = unbounded_channel();
+        let (sync_sender, sync_receiver) = unbounded_channel();
+        Self {
+            context: Arc::new(_InProcessContext {
+                node_id,
+                handler,
+                notify: stop_notify,
+                nodes,
+                faults: Default::default(),
+                async_sender,
+                async_receiver: StdMutex::new(Some(async_receiver)),
+                sync_sender,
+                sync_receiver: StdMutex::new(Some(sync_receiver)),
+            }),
+        }
+    }
+
+    /// Connect a tested node to the player, and return the inbox of the node.
+    /// Connecting a node again replaces its inbox, for example, when the node restarts.
+    pub fn connect_node<M: MsgTrait + 'static>(&self, node_id: NID) -> NodeInbox<M> {
+        let (sender, receiver) = unbounded_channel();
+        let mut nodes = self.context.nodes.nodes.lock().unwrap();
+        let _ = nodes.insert(node_id, sender);
+        NodeInbox {
+            receiver,
+            _phantom: Default::default(),
+        }
+    }
+
+    pub fn new_async_driver(&self, node_id: NID) -> Res<Arc<dyn AsyncActionDriver>> {
+        let driver = Arc::new(AsyncActionDriverImpl::new(
+            node_id,
+            self.context.node_id,
+            self.context.async_sender.clone()));
+        Ok(driver)
+    }
+
+    /// The synchronous driver blocks the calling thread, it must not be called by a task of the
+    /// runtime on which the player runs
+    pub fn new_sync_driver(&self, node_id: NID) -> Res<Arc<dyn SyncActionDriver>> {
+        let driver = Arc::new(SyncActionDriverImpl::new(
+            node_id,
+            self.context.node_id,
+            self.context.sync_sender.clone()));
+        Ok(driver)
+    }
+
+    /// The message faults delivered by the player to node `node_id`
+    pub fn message_faults(&self, node_id: NID) -> Arc<MessageFaults> {
+        self.context.node_faults(node_id)
+    }
+
+    /// Spawn the tasks of the player on the local set, the tasks run when the local set runs
+    pub fn run_local(&self, local_set: &LocalSet) {
+        let h = self.context.handler.clone();
+        let notify = self.context.notify.clone();
+        local_set.spawn_local(async move {
+            spawn_local_task(notify, "dtm handler", async move {
+                let _ = h.async_run().await;
+            }).unwrap();
+        });
+        let c = self.context.clone();
+        let notify = self.context.notify.clone();
+        local_set.spawn_local(async move {
+            spawn_local_task(notify, "dtm in process message loop", async move {
+                c.message_loop().await
+            }).unwrap();
+        });
+    }
+
+    pub async fn start_dtm_test(
+        &self,
+        trace_index: u64,
+        input: Arc<dyn ActionIncoming>,
+    ) -> Res<oneshot::Receiver<TraceVerdict>> {
+        self.context.handler.begin_run_test(trace_index, input).await
+    }
+
+    pub fn stop(&self) {
+        self.context.notify.notify_all();
+    }
+}
+
+impl _InProcessContext {
+    fn node_faults(&self, node_id: NID) -> Arc<MessageFaults> {
+        let mut faults = self.faults.lock().unwrap();
+        faults.entry(node_id).or_default().clone()
+    }
+
+    fn take_receivers(&self) -> Res<(UnboundedReceiver<AsyncRequest>, UnboundedReceiver<SyncRequest>)> {
+        let async_receiver = res_option(self.async_receiver.lock().unwrap().take())?;
+        let sync_receiver = res_option(self.sync_receiver.lock().unwrap().take())?;
+        Ok((async_receiver, sync_receiver))
+    }
+
+    async fn message_loop(&self) -> Res<()> {
+        let (mut async_receiver, mut sync_receiver) = self.take_receivers()?;
+        let (response_sender, mut response_receiver) = unbounded_channel();
+        let mut async_senders = HashMap::new();
+        let mut sync_senders = HashMap::new();
+        loop {
+            select! {
+                opt = async_receiver.recv() => {
+                    let (m, s) = res_option(opt)?;
+                    let _ = async_senders.insert(m.payload_ref().id()?, s);
+                    self.handle_request(m, response_sender.clone())?;
+                }
+                opt = sync_receiver.recv() => {
+                    let (m, s) = res_option(opt)?;
+                    let _ = sync_senders.insert(m.payload_ref().id()?, s);
+                    self.handle_request(m, response_sender.clone())?;
+                }
+                opt = response_receiver.recv() => {
+                    let m = res_option(opt)?;
+                    let (from, to, m) = (m.source(), m.dest(), m.payload());
+                    if let MessageControl::ActionACK { faults, .. } = &m {
+                        self.node_faults(to).add(faults);
+                    }
+                    let id = m.id()?;
+                    let response = Message::new(m, to, from);
+                    if let Some(s) = async_senders.remove(&id) {
+                        let _ = s.send(response);
+                    } else if let Some(s) = sync_senders.remove(&id) {
+                        let _ = s.send(response);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_request(
+        &self,
+        m: Message<MessageControl>,
+        response_sender: UnboundedSender<Message<MessageControl>>,
+    ) -> Res<()> {
+        let h = self.handler.clone();
+        spawn_local_task(
+            self.notify.clone(),
+            format!("dtm handle message {:?}", m).as_str(),
+            async move {
+                h.handle_one_message(m.source(), m.dest(), m.payload(), response_sender).await
+            })?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessageToNode for MessageToNodeChannel {
+    async fn send(&self, message: Message<SerdeJsonString>) -> Res<()> {
+        let dest = message.dest();
+        let nodes = self.nodes.lock().unwrap();
+        let sender = match nodes.get(&dest) {
+            Some(s) => { s }
+            None => {
+                return Err(ET::IOError(format!("tested node {} is not connected", dest)));
+            }
+        };
+        match sender.send(message) {
+            Ok(()) => { Ok(()) }
+            Err(e) => { Err(ET::TokioSenderError(e.to_string())) }
+        }
+    }
+}
+
+impl<M: MsgTrait + 'static> NodeInbox<M> {
+    /// Receive the next message, return `ET::EOF` after the player is dropped
+    pub async fn receive(&mut self) -> Res<Message<M>> {
+        let m = match self.receiver.recv().await {
+            Some(m) => { m }
+            None => { return Err(ET::EOF); }
+        };
+        // the payload is the message of the Input action in JSON
+        res_serde(serde_json::from_str(m.payload_ref().to_string().as_str()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use bincode::{Decode, Encode};
+    use scupt_net::notifier::Notifier;
+    use scupt_util::message::{Message, MsgTrait};
+    use scupt_util::res::Res;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use tokio::runtime::Builder;
+    use tokio::task::LocalSet;
+
+    use crate::action::action_message::ActionMessage;
+    use crate::action::action_type::{ActionBeginEnd, ActionType};
+    use crate::action::trace::Trace;
+    use crate::dtm::action_input_json::ActionInputJson;
+    use crate::dtm::async_action_driver::AsyncActionDriver;
+    use crate::dtm::automata::{automata_clear, automata_setup_in_process, automata_timer};
+    use crate::dtm::dtm_in_process::{DTMInProcess, NodeInbox};
+    use crate::dtm::dtm_player::TestOption;
+    use crate::dtm::state_projection::FieldProjection;
+
+    #[derive(Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize, Decode, Encode)]
+    enum EchoMsg {
+        Ping,
+        Pong,
+        Pang,
+    }
+
+    impl MsgTrait for EchoMsg {}
+
+    #[derive(Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize, Decode, Encode)]
+    enum KvMsg {
+        Set { value: u64, version: u64 },
+        State { value: u64, version: u64 },
+    }
+
+    impl MsgTrait for KvMsg {}
+
+    #[derive(Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize, Decode, Encode)]
+    enum TimerMsg {
+        ElectionTimeout(u64),
+    }
+
+    impl MsgTrait for TimerMsg {}
+
+    // receive a message and reply
+    async fn echo(
+        inbox: &mut NodeInbox<EchoMsg>,
+        driver: &Arc<dyn AsyncActionDriver>,
+        reply: EchoMsg,
+    ) -> Res<()> {
+        let m = inbox.receive().await?;
+        let payload = serde_json::to_string(m.payload_ref()).unwrap();
+        driver.action(ActionType::Input, ActionBeginEnd::End, m.source(), m.dest(), payload).await?;
+        let payload = serde_json::to_string(&reply).unwrap();
+        driver.action(ActionType::Output, ActionBeginEnd::Begin, m.dest(), m.source(), payload).await
+    }
+
+    #[test]
+    fn test_dtm_in_process() {
+        let trace = Trace {
+            actions: vec![
+                ActionMessage::Input(Message::new(EchoMsg::Ping, 1, 2)),
+                ActionMessage::Output(Message::new(EchoMsg::Pong, 2, 1)),
+            ]
+        };
+        let json = trace.to_json_string().unwrap();
+        let option = TestOption::new().set_seconds_wait_message_timeout(1);
+        let player = DTMInProcess::new(100, Notifier::new(), option);
+        let mut inbox = player.connect_node::<EchoMsg>(2);
+        let driver = player.new_async_driver(2).unwrap();
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let local = LocalSet::new();
+        player.run_local(&local);
+        local.block_on(&runtime, async move {
+            let input = Arc::new(ActionInputJson::from_json_string(json.clone()).unwrap());
+            let r = player.start_dtm_test(1, input).await.unwrap();
+            echo(&mut inbox, &driver, EchoMsg::Pong).await.unwrap();
+            let verdict = r.await.unwrap();
+            assert!(verdict.is_passed(), "{}", verdict);
+
+            // the node replies a message not in the trace
+            let input = Arc::new(ActionInputJson::from_json_string(json).unwrap());
+            let r = player.start_dtm_test(2, input).await.unwrap();
+            let node = async move {
+                let _ = echo(&mut inbox, &driver, EchoMsg::Pang).await;
+            };
+            tokio::task::spawn_local(node);
+            let verdict = r.await.unwrap();
+            assert!(!verdict.is_passed());
+            assert_eq!(verdict.step().unwrap().seq_no, 2);
+            player.stop();
+        });
+    }
+
+    // apply the Setup action and report the state at the Check action, whose version differs from
+    // the spec's
+    async fn kv_node(inbox: &mut NodeInbox<KvMsg>, driver: &Arc<dyn AsyncActionDriver>) -> Res<()> {
+        let m = inbox.receive().await?;
+        let payload = serde_json::to_string(m.payload_ref()).unwrap();
+        driver.action(ActionType::Setup, ActionBeginEnd::End, m.source(), m.dest(), payload).await?;
+        let value = match m.payload_ref() {
+            KvMsg::Set { value, .. } => { *value }
+            KvMsg::State { value, .. } => { *value }
+        };
+        let m = inbox.receive().await?;
+        let payload = serde_json::to_string(&KvMsg::State { value, version: 7 }).unwrap();
+        driver.action(ActionType::Check, ActionBeginEnd::End, m.source(), m.dest(), payload).await
+    }
+
+    fn run_setup_check(option: TestOption) -> bool {
+        let trace = Trace {
+            actions: vec![
+                ActionMessage::Setup(Message::new(KvMsg::Set { value: 1, version: 0 }, 1, 2)),
+                ActionMessage::Check(Message::new(KvMsg::State { value: 1, version: 0 }, 1, 2)),
+            ]
+        };
+        let json = trace.to_json_string().unwrap();
+        let player = DTMInProcess::new(100, Notifier::new(), option);
+        let mut inbox = player.connect_node::<KvMsg>(2);
+        let driver = player.new_async_driver(2).unwrap();
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let local = LocalSet::new();
+        player.run_local(&local);
+        local.block_on(&runtime, async move {
+            let input = Arc::new(ActionInputJson::from_json_string(json).unwrap());
+            let r = player.start_dtm_test(1, input).await.unwrap();
+            let node = async move {
+                let _ = kv_node(&mut inbox, &driver).await;
+            };
+            tokio::task::spawn_local(node);
+            let verdict = r.await.unwrap();
+            player.stop();
+            verdict.is_passed()
+        })
+    }
+
+    #[test]
+    fn test_setup_check() {
+        // the whole state is compared by default
+        let option = TestOption::new().set_seconds_wait_message_timeout(1);
+        assert!(!run_setup_check(option));
+        let projection = FieldProjection::new(vec!["/State/value".to_string()]);
+        let option = TestOption::new()
+            .set_seconds_wait_message_timeout(1)
+            .set_state_projection(Arc::new(projection));
+        assert!(run_setup_check(option));
+    }
+
+    #[test]
+    fn test_choose() {
+        // the spec picks 300 as the election timeout of node 2
+        let trace = Trace {
+            actions: vec![
+                ActionMessage::Internal(Message::new(TimerMsg::ElectionTimeout(300), 2, 2)),
+            ]
+        };
+        let json = trace.to_json_string().unwrap();
+        let option = TestOption::new().set_seconds_wait_message_timeout(1);
+        let player = DTMInProcess::new(100, Notifier::new(), option);
+        let _inbox = player.connect_node::<TimerMsg>(2);
+        let driver = player.new_async_driver(2).unwrap();
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let local = LocalSet::new();
+        player.run_local(&local);
+        local.block_on(&runtime, async move {
+            let input = Arc::new(ActionInputJson::from_json_string(json).unwrap());
+            let r = player.start_dtm_test(1, input).await.unwrap();
+            let candidates = vec!["150".to_string(), "300".to_string()];
+            let value = driver.choose("ElectionTimeout".to_string(), candidates).await.unwrap();
+            assert_eq!(value, Some("300".to_string()));
+            let timeout: u64 = serde_json::from_str(value.unwrap().as_str()).unwrap();
+            let payload = serde_json::to_string(&TimerMsg::ElectionTimeout(timeout)).unwrap();
+            for begin_end in [ActionBeginEnd::Begin, ActionBeginEnd::End] {
+                driver.action(ActionType::Internal, begin_end, 2, 2, payload.clone()).await.unwrap();
+            }
+            let verdict = r.await.unwrap();
+            assert!(verdict.is_passed(), "{}", verdict);
+
+            // no choice is offered after the trace finished
+            let value = driver.choose("ElectionTimeout".to_string(), vec![]).await.unwrap();
+            assert_eq!(value, None);
+            player.stop();
+        });
+    }
+
+    #[test]
+    fn test_virtual_clock() {
+        // the ticks are enacted after the node starts the timer
+        let json = json!({"actions": [
+            {"Internal": {"source": 2, "dest": 2, "payload": {"StartTimer": 100}}},
+            {"Internal": {"source": 2, "dest": 2, "payload": {"DTMTesting": {"Tick": 50}}}},
+            {"Internal": {"source": 2, "dest": 2, "payload": {"DTMTesting": {"Tick": 50}}}},
+            {"Internal": {"source": 2, "dest": 2, "payload": {"ElectionTimeout": 100}}},
+        ]}).to_string();
+        let option = TestOption::new().set_seconds_wait_message_timeout(5);
+        let player = DTMInProcess::new(100, Notifier::new(), option);
+        let _inbox = player.connect_node::<TimerMsg>(2);
+        let driver = player.new_async_driver(2).unwrap();
+        let name = "test_dtm_in_process_virtual_clock";
+        automata_setup_in_process(name, 2, &player);
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let local = LocalSet::new();
+        player.run_local(&local);
+        local.block_on(&runtime, async move {
+            let input = Arc::new(ActionInputJson::from_json_string(json).unwrap());
+            let r = player.start_dtm_test(1, input).await.unwrap();
+            let payload = json!({"StartTimer": 100}).to_string();
+            driver.action(ActionType::Internal, ActionBeginEnd::Begin, 2, 2, payload.clone()).await.unwrap();
+            let mut timer = automata_timer(name, Duration::from_millis(100));
+            assert_eq!(timer.tick().await, 0);
+            driver.action(ActionType::Internal, ActionBeginEnd::End, 2, 2, payload).await.unwrap();
+            // the timeout fires after the two ticks of the trace
+            let now = timer.tick().await;
+            assert_eq!(now, 100);
+            let payload = serde_json::to_string(&TimerMsg::ElectionTimeout(now)).unwrap();
+            for begin_end in [ActionBeginEnd::Begin, ActionBeginEnd::End] {
+                driver.action(ActionType::Internal, begin_end, 2, 2, payload.clone()).await.unwrap();
+            }
+            let verdict = r.await.unwrap();
+            assert!(verdict.is_passed(), "{}", verdict);
+            player.stop();
+        });
+        automata_clear(name);
+    }
+}
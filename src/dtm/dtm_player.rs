@@ -1,18 +1,28 @@
 use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use scupt_net::notifier::Notifier;
 use scupt_net::task::spawn_local_task;
+use scupt_util::error_type::ET;
 use scupt_util::node_id::NID;
 use scupt_util::res::Res;
 use scupt_util::res_of::res_io;
 use tokio::runtime::Builder;
 use tokio::task::LocalSet;
+use tokio::time::sleep;
 use tracing::{debug, error};
 
 use crate::dtm::action_incoming::ActionIncoming;
+use crate::dtm::action_recorder::ActionRecorder;
+use crate::dtm::dtm_proxy::ProxyOption;
 use crate::dtm::dtm_server::DTMServer;
+use crate::dtm::node_supervisor::NodeSupervisor;
+use crate::dtm::state_projection::StateProjection;
+use crate::dtm::step_control::StepController;
+use crate::dtm::trace_verdict::TraceVerdict;
+use crate::trace::spec_graph::SpecGraph;
 
 /// Deterministic  Player
 pub struct DTMPlayer {}
@@ -30,8 +40,39 @@ pub struct TestOption {
     pub output_action_sequential: bool,
 
     /// after `seconds_wait_message_timeout`(default is 30) seconds, report as an inconsistency
-    /// trace error
+    /// trace error.
+    /// Only the time during which the tested nodes are alive and not paused(such as stopped by a
+    /// debugger) is counted.
     pub seconds_wait_message_timeout: u64,
+
+    /// when debug_mode is enable, the timeout is suspended and the player waits as long as it
+    /// takes
+    pub debug_mode: bool,
+
+    /// when step_controller is set, the player stops before the actions and waits for the
+    /// commands of the controller
+    pub step_controller: Option<Arc<StepController>>,
+
+    /// when node_supervisor is set, the player watches the tested nodes it launched, and stops
+    /// the trace as soon as one of them crashed
+    pub node_supervisor: Option<Arc<NodeSupervisor>>,
+
+    /// when proxy is set, the messages between the tested nodes go through the player, and the
+    /// player forwards a message when the trace reaches the matching Input action
+    pub proxy: Option<ProxyOption>,
+
+    /// when spec_graph is set, the player runs as a conformance monitor, it ignores the trace
+    /// and accepts any action which is an outgoing edge of the current states of the graph
+    pub spec_graph: Option<Arc<SpecGraph>>,
+
+    /// when recorder is set, the player ignores the trace, and acknowledges and records every
+    /// action of the tested nodes
+    pub recorder: Option<Arc<ActionRecorder>>,
+
+    /// when state_projection is set, a Check action passes when the projection of the state the
+    /// node observes equals the projection of the state in the trace, otherwise, the two states
+    /// must be equal
+    pub state_projection: Option<Arc<dyn StateProjection>>,
 }
 
 
@@ -41,6 +82,13 @@ impl TestOption {
             wait_both_begin_and_end_action: false,
             output_action_sequential: false,
             seconds_wait_message_timeout: 30,
+            debug_mode: false,
+            step_controller: None,
+            node_supervisor: None,
+            proxy: None,
+            spec_graph: None,
+            recorder: None,
+            state_projection: None,
         }
     }
 
@@ -61,6 +109,48 @@ impl TestOption {
         s.seconds_wait_message_timeout = seconds;
         s
     }
+
+    pub fn set_debug_mode(self, enable: bool) -> Self {
+        let mut s = self;
+        s.debug_mode = enable;
+        s
+    }
+
+    pub fn set_step_controller(self, controller: Arc<StepController>) -> Self {
+        let mut s = self;
+        s.step_controller = Some(controller);
+        s
+    }
+
+    pub fn set_node_supervisor(self, supervisor: Arc<NodeSupervisor>) -> Self {
+        let mut s = self;
+        s.node_supervisor = Some(supervisor);
+        s
+    }
+
+    pub fn set_proxy(self, proxy: ProxyOption) -> Self {
+        let mut s = self;
+        s.proxy = Some(proxy);
+        s
+    }
+
+    pub fn set_spec_graph(self, graph: Arc<SpecGraph>) -> Self {
+        let mut s = self;
+        s.spec_graph = Some(graph);
+        s
+    }
+
+    pub fn set_recorder(self, recorder: Arc<ActionRecorder>) -> Self {
+        let mut s = self;
+        s.recorder = Some(recorder);
+        s
+    }
+
+    pub fn set_state_projection(self, projection: Arc<dyn StateProjection>) -> Self {
+        let mut s = self;
+        s.state_projection = Some(projection);
+        s
+    }
 }
 
 impl Default for TestOption {
@@ -85,6 +175,8 @@ impl DTMPlayer
     ///
     /// `peers` is a map of the tested node's node id and their network addresses
     ///
+    /// `trace_index` is the index of the trace, which is reported in the verdict
+    ///
     /// `action_incoming` is a Arc pointer of ActionIncoming trait which the player can read
     ///     a sequence of actions(a trace)
     ///
@@ -93,15 +185,19 @@ impl DTMPlayer
     /// `option` is option parameters for the testing
     ///
     /// `fn_done` invoke when testing finish
+    ///
+    /// Return the verdict of the trace, a divergence or a timeout is reported in the verdict
+    /// rather than as an error
     pub fn run_trace<F>(
         player_node_id: NID,
         player_addr: SocketAddr,
         peers: HashMap<NID, SocketAddr>,
+        trace_index: u64,
         action_incoming: Arc<dyn ActionIncoming>,
         notifier: Notifier,
         option: TestOption,
         fn_done: F,
-    ) -> Res<()>
+    ) -> Res<TraceVerdict>
         where F: Fn() + 'static
     {
         let r_build = Builder::new_current_thread()
@@ -110,6 +206,7 @@ impl DTMPlayer
         let r = res_io(r_build)?;
         let runtime = Arc::new(r);
         let name = format!("player_{}", player_node_id);
+        let opt_supervisor = option.node_supervisor.clone();
         let s =
             DTMServer::new(
                 player_node_id,
@@ -119,31 +216,14 @@ impl DTMPlayer
             )?;
         let server = Arc::new(s);
         let ls = LocalSet::new();
+        let result: Arc<Mutex<Option<TraceVerdict>>> = Default::default();
         {
             let server = server.clone();
+            let result = result.clone();
             let future = async move {
-                let r1 = server.start_network(
-                    player_addr,
-                    peers).await;
-                match r1 {
-                    Ok(()) => {}
-                    Err(e) => {
-                        error!("{}", e.to_string());
-                        return;
-                    }
-                }
-                let r2 = server.start_dtm_test(action_incoming).await;
-                match r2 {
-                    Ok(r) => {
-                        let _ = r.await;
-                    }
-                    Err(e) => {
-                        error!("dtm player error: {}", e.to_string());
-                        return;
-                    }
-                }
-
-                debug!("dtm player handle test done");
+                let verdict = Self::start_test(server, player_addr, peers, trace_index, action_incoming).await;
+                debug!("dtm player handle test done, {}", verdict);
+                *result.lock().unwrap() = Some(verdict);
                 fn_done();
             };
             let _n = notifier.clone();
@@ -151,8 +231,88 @@ impl DTMPlayer
                 spawn_local_task(_n, "dtm", future).unwrap();
             });
         }
+        if let Some(supervisor) = &opt_supervisor {
+            let supervisor = supervisor.clone();
+            let _n = notifier.clone();
+            let n = notifier.clone();
+            let future = async move {
+                Self::watch_node(supervisor, n).await;
+            };
+            ls.spawn_local(async {
+                spawn_local_task(_n, "node supervisor", future).unwrap();
+            });
+        }
         server.run(Some(ls), runtime);
         debug!("dtm player server stopped");
-        Ok(())
+        let opt_verdict = result.lock().unwrap().take();
+        let verdict = match opt_verdict {
+            Some(verdict) => { verdict }
+            None => {
+                TraceVerdict::protocol_error(
+                    trace_index, None, None,
+                    ET::NoneOption)
+            }
+        };
+        // a node crash is the cause of the failure it leads to
+        if !verdict.is_passed() {
+            if let Some(supervisor) = &opt_supervisor {
+                if let Some((node_id, message)) = supervisor.poll_crashed() {
+                    return Ok(TraceVerdict::NodeCrashed {
+                        trace_index,
+                        node_id,
+                        message,
+                    });
+                }
+            }
+        }
+        Ok(verdict)
+    }
+
+    /// Stop the player when a tested node crashed
+    async fn watch_node(supervisor: Arc<NodeSupervisor>, notifier: Notifier) {
+        loop {
+            sleep(Duration::from_millis(100)).await;
+            if let Some((node_id, message)) = supervisor.poll_crashed() {
+                error!("tested node {} crashed, {}, stop the player", node_id, message);
+                notifier.task_notify_all();
+                break;
+            }
+        }
+    }
+
+    async fn start_test(
+        server: Arc<DTMServer>,
+        player_addr: SocketAddr,
+        peers: HashMap<NID, SocketAddr>,
+        trace_index: u64,
+        action_incoming: Arc<dyn ActionIncoming>,
+    ) -> TraceVerdict {
+        let r1 = server.start_network(
+            player_addr,
+            peers).await;
+        match r1 {
+            Ok(()) => {}
+            Err(e) => {
+                error!("{}", e.to_string());
+                return TraceVerdict::protocol_error(trace_index, None, None, e);
+            }
+        }
+        let r2 = server.start_dtm_test(trace_index, action_incoming).await;
+        match r2 {
+            Ok(r) => {
+                match r.await {
+                    Ok(verdict) => { verdict }
+                    Err(e) => {
+                        TraceVerdict::protocol_error(
+                            trace_index, None, None,
+                            ET::RecvError(e.to_string()))
+                    }
+                }
+            }
+            Err(e) => {
+                error!("dtm player error: {}", e.to_string());
+                TraceVerdict::protocol_error(trace_index, None, None, e)
+            }
+        }
     }
 }
\ No newline at end of file
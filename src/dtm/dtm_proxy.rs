@@ -0,0 +1,361 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use scupt_net::notifier::Notifier;
+use scupt_net::task::spawn_local_task;
+use scupt_util::error_type::ET;
+use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+use scupt_util::res_of::res_io;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::select;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Notify;
+use tracing::{debug, error, trace};
+
+use crate::dtm::liveness::Liveness;
+
+/// How to split the bytes of a connection into messages
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageFraming {
+    /// a message is a 4 bytes big-endian length, followed by the bytes of this length
+    #[default]
+    LengthDelimited,
+    /// a message is a line ended with `\n`
+    Line,
+}
+
+impl MessageFraming {
+    // the message payload of a frame, the bytes without the length or the line end are the JSON
+    // of the payload, return None if it is not JSON
+    fn payload(&self, bytes: &[u8]) -> Option<Value> {
+        let body = match self {
+            MessageFraming::LengthDelimited => { bytes.get(4..)? }
+            MessageFraming::Line => { bytes.strip_suffix(b"\n").unwrap_or(bytes) }
+        };
+        serde_json::from_slice(body).ok()
+    }
+}
+
+/// The connections from node `source` to node `dest` go through the player.
+/// Node `source` uses `listen_addr` as the address of node `dest`, on which the player listens,
+/// and the player connects to `dest_addr`, the real address of node `dest`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ProxyLink {
+    pub source: NID,
+    pub dest: NID,
+    pub listen_addr: SocketAddr,
+    pub dest_addr: SocketAddr,
+    /// the messages from `dest` back to `source` on these connections are also held by the
+    /// player, otherwise they are passed through at once
+    #[serde(default)]
+    pub reverse: bool,
+}
+
+/// Proxy mode, in which the player holds the messages between the tested nodes, and forwards a
+/// message only when the trace reaches the matching Input action.
+/// A message matches an Input action when its bytes, without the length or the line end, are the
+/// JSON of the message payload of the action.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ProxyOption {
+    #[serde(default)]
+    pub framing: MessageFraming,
+    pub link: Vec<ProxyLink>,
+}
+
+struct Frame {
+    bytes: Vec<u8>,
+    // the writer of the connection to the receiver
+    writer: UnboundedSender<Vec<u8>>,
+}
+
+// the messages held, which are sent from a node to another node
+#[derive(Default)]
+struct Direction {
+    frames: Mutex<VecDeque<Frame>>,
+    notify: Notify,
+}
+
+pub struct DTMProxy {
+    option: ProxyOption,
+    notify: Notifier,
+    directions: Mutex<HashMap<(NID, NID), Arc<Direction>>>,
+}
+
+impl DTMProxy {
+    pub fn new(option: ProxyOption, notify: Notifier) -> Self {
+        Self {
+            option,
+            notify,
+            directions: Default::default(),
+        }
+    }
+
+    /// Are the messages from `source` to `dest` forwarded by the player.
+    /// The direction from `dest` back to `source` of a link is forwarded only if it is `reverse`.
+    pub fn is_proxied(&self, source: NID, dest: NID) -> bool {
+        self.option.link.iter().any(|l| {
+            (l.source == source && l.dest == dest) || (l.reverse && l.source == dest && l.dest == source)
+        })
+    }
+
+    /// Listen on the proxy addresses
+    pub async fn start(self: &Arc<Self>) -> Res<()> {
+        for link in self.option.link.iter() {
+            let listener = res_io(TcpListener::bind(link.listen_addr).await)?;
+            debug!("proxy {}->{} listen on {}", link.source, link.dest, link.listen_addr);
+            let proxy = self.clone();
+            let l = link.clone();
+            spawn_local_task(
+                self.notify.clone(),
+                format!("proxy_accept_{}_{}", link.source, link.dest).as_str(),
+                async move {
+                    proxy.accept_loop(l, listener).await
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Forward the first message held from `source` to `dest` whose payload is `payload`, wait
+    /// until the message arrives.
+    /// Return false if timeout, and an error if timeout while other messages are held.
+    pub(crate) async fn release(&self, source: NID, dest: NID, payload: &Value, liveness: &Liveness) -> Res<bool> {
+        let direction = self.direction(source, dest);
+        loop {
+            let opt_frame = {
+                let mut frames = direction.frames.lock().unwrap();
+                let opt_i = frames.iter().position(|f| {
+                    self.option.framing.payload(&f.bytes).as_ref() == Some(payload)
+                });
+                opt_i.and_then(|i| { frames.remove(i) })
+            };
+            if let Some(frame) = opt_frame {
+                trace!("proxy forward message {}->{}, {} bytes", source, dest, frame.bytes.len());
+                // the connection closed if error, the receiver would find it
+                let _ = frame.writer.send(frame.bytes);
+                return Ok(true);
+            }
+            select! {
+                _ = direction.notify.notified() => {}
+                _ = liveness.wait_timeout() => {
+                    let held = direction.frames.lock().unwrap().len();
+                    return if held == 0 {
+                        Ok(false)
+                    } else {
+                        Err(ET::ParseError(format!(
+                            "none of the {} messages held from {} to {} matches the input message {}",
+                            held, source, dest, payload)))
+                    };
+                }
+            }
+        }
+    }
+
+    fn direction(&self, source: NID, dest: NID) -> Arc<Direction> {
+        let mut map = self.directions.lock().unwrap();
+        map.entry((source, dest)).or_default().clone()
+    }
+
+    async fn accept_loop(self: Arc<Self>, link: ProxyLink, listener: TcpListener) -> Res<()> {
+        loop {
+            let (stream, _) = res_io(listener.accept().await)?;
+            let r = self.clone().serve_connection(link.clone(), stream).await;
+            if let Err(e) = r {
+                error!("proxy {}->{} error, {}", link.source, link.dest, e);
+            }
+        }
+    }
+
+    async fn serve_connection(self: Arc<Self>, link: ProxyLink, source_stream: TcpStream) -> Res<()> {
+        let dest_stream = res_io(TcpStream::connect(link.dest_addr).await)?;
+        let (source_read, source_write) = source_stream.into_split();
+        let (dest_read, dest_write) = dest_stream.into_split();
+        let (to_dest, to_dest_r) = unbounded_channel();
+        let (to_source, to_source_r) = unbounded_channel();
+        let name = format!("proxy_{}_{}", link.source, link.dest);
+        spawn_local_task(self.notify.clone(), format!("{}_write_dest", name).as_str(),
+                         Self::write_loop(to_dest_r, dest_write))?;
+        spawn_local_task(self.notify.clone(), format!("{}_write_source", name).as_str(),
+                         Self::write_loop(to_source_r, source_write))?;
+        spawn_local_task(self.notify.clone(), format!("{}_read_source", name).as_str(),
+                         self.clone().read_loop(link.source, link.dest, true, source_read, to_dest))?;
+        spawn_local_task(self.notify.clone(), format!("{}_read_dest", name).as_str(),
+                         self.clone().read_loop(link.dest, link.source, link.reverse, dest_read, to_source))?;
+        Ok(())
+    }
+
+    async fn read_loop(
+        self: Arc<Self>,
+        source: NID,
+        dest: NID,
+        hold: bool,
+        reader: OwnedReadHalf,
+        writer: UnboundedSender<Vec<u8>>,
+    ) -> Res<()> {
+        let direction = self.direction(source, dest);
+        let mut reader = BufReader::new(reader);
+        loop {
+            let opt_bytes = Self::read_frame(&mut reader, self.option.framing).await?;
+            let bytes = match opt_bytes {
+                Some(b) => { b }
+                None => { return Ok(()); }
+            };
+            if !hold {
+                trace!("proxy pass message {}->{}, {} bytes", source, dest, bytes.len());
+                // the connection closed if error, the receiver would find it
+                let _ = writer.send(bytes);
+                continue;
+            }
+            trace!("proxy hold message {}->{}, {} bytes", source, dest, bytes.len());
+            direction.frames.lock().unwrap().push_back(Frame {
+                bytes,
+                writer: writer.clone(),
+            });
+            direction.notify.notify_one();
+        }
+    }
+
+    async fn write_loop(mut receiver: UnboundedReceiver<Vec<u8>>, mut writer: OwnedWriteHalf) -> Res<()> {
+        while let Some(bytes) = receiver.recv().await {
+            res_io(writer.write_all(&bytes).await)?;
+        }
+        Ok(())
+    }
+
+    /// Read the bytes of a message, return None if the connection closed
+    async fn read_frame(reader: &mut BufReader<OwnedReadHalf>, framing: MessageFraming) -> Res<Option<Vec<u8>>> {
+        match framing {
+            MessageFraming::LengthDelimited => {
+                let mut header = [0u8; 4];
+                match reader.read_exact(&mut header).await {
+                    Ok(_) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => { return Ok(None); }
+                    Err(e) => { return res_io(Err(e)); }
+                }
+                let len = u32::from_be_bytes(header) as usize;
+                let mut bytes = header.to_vec();
+                bytes.resize(4 + len, 0);
+                res_io(reader.read_exact(&mut bytes[4..]).await)?;
+                Ok(Some(bytes))
+            }
+            MessageFraming::Line => {
+                let mut bytes = vec![];
+                let n = res_io(reader.read_until(b'\n', &mut bytes).await)?;
+                if n == 0 {
+                    Ok(None)
+                } else {
+                    Ok(Some(bytes))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use scupt_net::notifier::Notifier;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::runtime::Builder;
+    use tokio::task::LocalSet;
+    use tokio::time::{Duration, timeout};
+
+    use crate::dtm::dtm_proxy::{DTMProxy, MessageFraming, ProxyLink, ProxyOption};
+    use crate::dtm::liveness::Liveness;
+
+    // a proxy of link 1->2, the connection from node 1, and the connection accepted by node 2
+    async fn connect_proxy(reverse: bool) -> (Arc<DTMProxy>, TcpStream, TcpStream) {
+        let dest_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listen_addr = proxy_listener.local_addr().unwrap();
+        drop(proxy_listener);
+        let option = ProxyOption {
+            framing: MessageFraming::Line,
+            link: vec![ProxyLink {
+                source: 1,
+                dest: 2,
+                listen_addr,
+                dest_addr: dest_listener.local_addr().unwrap(),
+                reverse,
+            }],
+        };
+        let proxy = Arc::new(DTMProxy::new(option, Notifier::new()));
+        proxy.start().await.unwrap();
+        let source = TcpStream::connect(listen_addr).await.unwrap();
+        let (dest, _) = dest_listener.accept().await.unwrap();
+        (proxy, source, dest)
+    }
+
+    #[test]
+    fn test_dtm_proxy() {
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let ls = LocalSet::new();
+        ls.block_on(&runtime, async {
+            let (proxy, mut source, mut dest) = connect_proxy(true).await;
+            assert!(proxy.is_proxied(1, 2));
+            assert!(proxy.is_proxied(2, 1));
+            assert!(!proxy.is_proxied(1, 3));
+
+            source.write_all(b"11\n12\n").await.unwrap();
+
+            // held until released
+            let mut buf = [0u8; 3];
+            assert!(timeout(Duration::from_millis(200), dest.read_exact(&mut buf)).await.is_err());
+
+            // released in the order of the trace, not the order of arriving
+            let liveness = Liveness::new(1, false);
+            assert!(proxy.release(1, 2, &json!(12), &liveness).await.unwrap());
+            dest.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"12\n");
+
+            dest.write_all(b"21\n").await.unwrap();
+            assert!(timeout(Duration::from_millis(200), source.read_exact(&mut buf)).await.is_err());
+            assert!(proxy.release(2, 1, &json!(21), &liveness).await.unwrap());
+            source.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"21\n");
+
+            assert!(proxy.release(1, 2, &json!(11), &liveness).await.unwrap());
+            dest.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"11\n");
+
+            // no message held
+            assert!(!proxy.release(1, 2, &json!(11), &liveness).await.unwrap());
+            // no message held matches
+            source.write_all(b"13\n").await.unwrap();
+            assert!(proxy.release(1, 2, &json!(11), &liveness).await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_dtm_proxy_one_direction() {
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let ls = LocalSet::new();
+        ls.block_on(&runtime, async {
+            let (proxy, mut source, mut dest) = connect_proxy(false).await;
+            assert!(proxy.is_proxied(1, 2));
+            assert!(!proxy.is_proxied(2, 1));
+
+            // the reverse direction is passed through without release
+            let mut buf = [0u8; 3];
+            dest.write_all(b"21\n").await.unwrap();
+            source.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"21\n");
+
+            source.write_all(b"11\n").await.unwrap();
+            assert!(timeout(Duration::from_millis(200), dest.read_exact(&mut buf)).await.is_err());
+            let liveness = Liveness::new(1, false);
+            assert!(proxy.release(1, 2, &json!(11), &liveness).await.unwrap());
+            dest.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"11\n");
+        });
+    }
+}
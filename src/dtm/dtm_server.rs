@@ -21,7 +21,9 @@ use tracing::{error, trace};
 use crate::dtm::action_incoming::ActionIncoming;
 use crate::dtm::dtm_player::TestOption;
 use crate::dtm::dtm_server_handler::DTMServerHandler;
+use crate::dtm::message_to_node::MessageToNodeNet;
 use crate::dtm::msg_ctrl::MessageControl;
+use crate::dtm::trace_verdict::TraceVerdict;
 
 type PlayerNodeServer = Node<
     MessageControl,
@@ -54,7 +56,7 @@ impl DTMServer {
             HandleEventDummy::default(),
             false,
             stop_notify.clone())?;
-        let node_sender = client_node.default_message_sender_async();
+        let node_sender = Arc::new(MessageToNodeNet::new(client_node.default_message_sender_async()));
         let h = DTMServerHandler::new(node_id, node_sender, stop_notify.clone(), option);
         let player_node: PlayerNodeServer = PlayerNodeServer::new(
             node_id,
@@ -129,6 +131,8 @@ impl DTMServer {
 
         trace!("serve dtm player, listen on address {}", simulate_bind_address.to_string());
 
+        self.handler.start_proxy().await?;
+
         self.start_connect_to_node(node_address).await?;
         Ok(())
     }
@@ -136,9 +140,10 @@ impl DTMServer {
     #[async_backtrace::framed]
     pub async fn start_dtm_test(
         &self,
-        input: Arc<dyn ActionIncoming>) -> Res<oneshot::Receiver<Res<()>>> {
+        trace_index: u64,
+        input: Arc<dyn ActionIncoming>) -> Res<oneshot::Receiver<TraceVerdict>> {
         let _ = task_trace!();
-        self.handler.begin_run_test(input).await
+        self.handler.begin_run_test(trace_index, input).await
     }
 
     fn run_handle_local(&self, local_set: &LocalSet) {
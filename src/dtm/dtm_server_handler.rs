@@ -1,16 +1,13 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::net::SocketAddr;
-use std::process::exit;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use scupt_net::endpoint_async::EndpointAsync;
 use scupt_net::handle_event::HandleEvent;
 use scupt_net::message_incoming::MessageIncoming;
-use scupt_net::message_sender_async::SenderAsync;
 use scupt_net::notifier::Notifier;
-use scupt_net::opt_send::OptSend;
 use scupt_net::task::spawn_local_task;
 use scupt_net::task_trace;
 use scupt_util::error_type::ET;
@@ -22,49 +19,64 @@ use serde_json::Value;
 use tokio::select;
 use tokio::sync::{mpsc, Mutex, oneshot};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
-use tokio::task::JoinHandle;
-use tokio::time::Duration;
-use tokio::time::sleep;
-use tracing::{error, Instrument, trace, trace_span};
+use tokio::task::{JoinHandle, spawn_blocking};
+use tracing::{debug, error, Instrument, trace, trace_span};
 
 use crate::action::action_json::ActionJson;
 use crate::action::action_type::ActionType;
 use crate::dtm::action_executor::ActionExecutor;
 use crate::dtm::action_incoming::ActionIncoming;
+use crate::dtm::action_recorder::ActionRecorder;
 use crate::dtm::action_waiter::ActionPrefixWaiter;
 use crate::dtm::dtm_player::TestOption;
+use crate::dtm::dtm_proxy::DTMProxy;
+use crate::dtm::fault::{Fault, FaultKind};
+use crate::dtm::liveness::Liveness;
+use crate::dtm::message_to_node::MessageToNode;
 use crate::dtm::msg_ctrl::MessageControl;
+use crate::dtm::node_supervisor::NodeSupervisor;
+use crate::dtm::spec_monitor::SpecMonitor;
+use crate::dtm::step_control::StepController;
+use crate::dtm::trace_verdict::{TraceStep, TraceVerdict};
+use crate::dtm::virtual_clock::VirtualClock;
 
 enum DTMCmd {
-    StartAction((Arc<dyn ActionIncoming>, oneshot::Sender<Res<()>>)),
+    StartAction((u64, Arc<dyn ActionIncoming>, oneshot::Sender<TraceVerdict>)),
 }
 
 struct Handler {
     output_action_sequential: bool,
     trace_in_one_sequence: bool,
     notify: Notifier,
-    node_sender: Arc<dyn SenderAsync<SerdeJsonString>>,
+    node_sender: Arc<dyn MessageToNode>,
     executor: Arc<ActionExecutor>,
+    step_controller: Option<Arc<StepController>>,
+    node_supervisor: Option<Arc<NodeSupervisor>>,
+    proxy: Option<Arc<DTMProxy>>,
+    monitor: Option<Arc<SpecMonitor>>,
+    recorder: Option<Arc<ActionRecorder>>,
     channel_s: Arc<mpsc::UnboundedSender<DTMCmd>>,
     channel_r: Arc<Mutex<mpsc::UnboundedReceiver<DTMCmd>>>,
 }
 
 pub struct DTMServerHandler {
     handler: Arc<Handler>,
-    seconds_timeout: u64,
     node_id: NID,
 }
 
 impl DTMServerHandler {
     pub fn new(
         node_id: NID,
-        node_sender: Arc<dyn SenderAsync<SerdeJsonString>>,
+        node_sender: Arc<dyn MessageToNode>,
         notify: Notifier,
         option: TestOption,
     ) -> DTMServerHandler {
         let (s, r) = mpsc::unbounded_channel::<
             DTMCmd>();
 
+        let proxy = option.proxy.map(|p| { Arc::new(DTMProxy::new(p, notify.clone())) });
+        let liveness = Liveness::new(option.seconds_wait_message_timeout, option.debug_mode);
+        let monitor = option.spec_graph.map(|g| { Arc::new(SpecMonitor::new(g, liveness.clone())) });
         let h = Handler {
             output_action_sequential: option.output_action_sequential,
             trace_in_one_sequence: option.wait_both_begin_and_end_action,
@@ -72,14 +84,19 @@ impl DTMServerHandler {
             node_sender,
             executor: Arc::new(ActionExecutor::new(
                 option.wait_both_begin_and_end_action,
-                option.seconds_wait_message_timeout,
+                option.state_projection,
+                liveness,
             )),
+            step_controller: option.step_controller,
+            node_supervisor: option.node_supervisor,
+            proxy,
+            monitor,
+            recorder: option.recorder,
             channel_s: Arc::new(s),
             channel_r: Arc::new(Mutex::new(r)),
         };
         Self {
             handler: Arc::new(h),
-            seconds_timeout: option.seconds_wait_message_timeout,
             node_id,
         }
     }
@@ -96,11 +113,23 @@ impl DTMServerHandler {
         }
     }
 
+    /// Listen on the proxy addresses in proxy mode
+    pub async fn start_proxy(&self) -> Res<()> {
+        if let Some(p) = &self.handler.proxy {
+            p.start().await?;
+        }
+        Ok(())
+    }
+
     #[async_backtrace::framed]
-    pub async fn begin_run_test(&self, input: Arc<dyn ActionIncoming>) -> Res<oneshot::Receiver<Res<()>>> {
+    pub async fn begin_run_test(
+        &self,
+        trace_index: u64,
+        input: Arc<dyn ActionIncoming>,
+    ) -> Res<oneshot::Receiver<TraceVerdict>> {
         let _ = task_trace!();
-        let (s, r) = oneshot::channel::<Res<()>>();
-        self.send_dtm_cmd(DTMCmd::StartAction((input, s)))?;
+        let (s, r) = oneshot::channel::<TraceVerdict>();
+        self.send_dtm_cmd(DTMCmd::StartAction((trace_index, input, s)))?;
         Ok(r)
     }
 
@@ -114,8 +143,8 @@ impl DTMServerHandler {
     #[async_backtrace::framed]
     async fn run_cmd(&self, cmd: DTMCmd) -> Res<()> {
         match cmd {
-            DTMCmd::StartAction((input, result_sender)) => {
-                self.run_read_input(input, result_sender).await?;
+            DTMCmd::StartAction((trace_index, input, result_sender)) => {
+                self.run_read_input(trace_index, input, result_sender).await?;
             }
         }
         Ok(())
@@ -124,91 +153,145 @@ impl DTMServerHandler {
     #[async_backtrace::framed]
     async fn run_read_input(
         &self,
+        trace_index: u64,
         input: Arc<dyn ActionIncoming>,
-        result_sender: oneshot::Sender<Res<()>>,
+        result_sender: oneshot::Sender<TraceVerdict>,
     ) -> Res<()> {
         trace!("run read input");
+        // the clocks of the nodes start at 0 for each trace
+        self.handler.executor.clock().reset();
+        if let Some(recorder) = &self.handler.recorder {
+            // the trace is not used when recording
+            recorder.wait_idle(self.handler.executor.liveness()).await;
+            debug!("run {} recorded {} actions", recorder.run_id(), recorder.num_recorded());
+            let _ = result_sender.send(TraceVerdict::Passed { trace_index });
+            return Ok(());
+        }
+        if let Some(monitor) = &self.handler.monitor {
+            // the trace is not used when monitoring
+            let verdict = monitor.verdict(trace_index).await;
+            let _ = result_sender.send(verdict);
+            return Ok(());
+        }
         let executor = self.handler.executor.clone();
         let sender = self.handler.node_sender.clone();
         let notify = self.handler.notify.clone();
-        let seconds_timeout = self.seconds_timeout;
         let r = Self::loop_read_input_action(
             self.node_id,
+            trace_index,
             notify, input, executor, sender,
             self.handler.output_action_sequential,
             !self.handler.trace_in_one_sequence,
-            seconds_timeout,
+            self.handler.step_controller.clone(),
+            self.handler.node_supervisor.clone(),
+            self.handler.proxy.clone(),
         ).await;
-        match &r {
-            Ok(()) => {}
+        let verdict = match r {
+            Ok(verdict) => { verdict }
             Err(e) => {
-                match e {
-                    ET::EOF => {}
-                    _ => { error!("{}", e.to_string()); }
-                }
+                error!("{}", e.to_string());
+                TraceVerdict::protocol_error(trace_index, None, None, e)
             }
+        };
+        if !verdict.is_passed() {
+            error!("{}", verdict);
         }
-        let _ = result_sender.send(r);
+        // the nodes blocked on the actions timeout go on after the verdict
+        self.handler.executor.release_timeout_actions().await;
+        let _ = result_sender.send(verdict);
         Ok(())
     }
 
     #[async_backtrace::framed]
     async fn loop_read_input_action(
         server_node_id: NID,
+        trace_index: u64,
         notify: Notifier,
         input: Arc<dyn ActionIncoming>,
         executor: Arc<ActionExecutor>,
-        sender: Arc<dyn SenderAsync<SerdeJsonString>>,
+        sender: Arc<dyn MessageToNode>,
         output_action_sequential: bool,
         per_node_trace: bool,
-        seconds_timeout: u64,
-    ) -> Res<()> {
+        step_controller: Option<Arc<StepController>>,
+        node_supervisor: Option<Arc<NodeSupervisor>>,
+        proxy: Option<Arc<DTMProxy>>,
+    ) -> Res<TraceVerdict> {
         let waiter = ActionPrefixWaiter::new();
-        if !per_node_trace {
-            let mut tasks = vec![];
-            let trace = Self::input_to_one_trace(input)?;
-            let r = Self::handle_trace(
-                server_node_id,
-                None, notify, trace, executor, sender,
-                output_action_sequential, seconds_timeout,
-                waiter.clone(),
-                &mut tasks).await;
-            for t in tasks {
-                let _ = t.await;
-            }
-            r?
+        let traces: Vec<_> = if !per_node_trace {
+            vec![(None, Self::input_to_one_trace(input)?)]
         } else {
-            let trace_map = Self::input_to_per_node_trace(input)?;
-            let mut join = vec![];
-            for (nid, trace) in trace_map {
-                let n = notify.clone();
-                let e = executor.clone();
-                let s = sender.clone();
-                let w = waiter.clone();
-                let f = async move {
-                    let mut tasks = vec![];
-                    let r = Self::handle_trace(
-                        server_node_id,
-                        Some(nid), n, trace, e, s, output_action_sequential, seconds_timeout,
-                        w,
-                        &mut tasks).await;
-                    for t in tasks {
-                        let _ = t.await;
-                    }
-                    r
-                };
-                let j = spawn_local_task(notify.clone(), format!("handle_trace_node_{:?}", nid).as_str(), f);
-                join.push(j);
-            }
-            for j in join {
-                let opt_r = j?.await;
-                match opt_r {
+            Self::input_to_per_node_trace(input)?
+                .into_iter()
+                .map(|(nid, trace)| { (Some(nid), trace) })
+                .collect()
+        };
+
+        // the first failure of any trace task stops the test
+        let (verdict_sender, mut verdict_receiver) = mpsc::unbounded_channel::<TraceVerdict>();
+        let mut join = vec![];
+        for (opt_nid, trace) in traces {
+            let n = notify.clone();
+            let e = executor.clone();
+            let s = sender.clone();
+            let w = waiter.clone();
+            let vs = verdict_sender.clone();
+            let c = step_controller.clone();
+            let ns = node_supervisor.clone();
+            let p = proxy.clone();
+            let f = async move {
+                let mut tasks = vec![];
+                let r = Self::handle_trace(
+                    server_node_id,
+                    trace_index,
+                    opt_nid, n, trace, e, s, output_action_sequential,
+                    w,
+                    c,
+                    ns,
+                    p,
+                    vs.clone(),
+                    &mut tasks).await;
+                for t in tasks {
+                    let _ = t.await;
+                }
+                if let Err(e) = r {
+                    let _ = vs.send(TraceVerdict::protocol_error(trace_index, None, None, e));
+                }
+            };
+            let name = match opt_nid {
+                Some(nid) => { format!("handle_trace_node_{:?}", nid) }
+                None => { "handle_trace".to_string() }
+            };
+            let j = spawn_local_task(notify.clone(), name.as_str(), f)?;
+            join.push(j);
+        }
+        let join_all = async {
+            for j in join.iter_mut() {
+                let r = j.await;
+                match r {
                     Ok(_) => {}
                     Err(e) => { error!("join handle node trace {:?}", e) }
                 }
             }
+        };
+        let opt_failure = select! {
+            _ = join_all => {
+                None
+            }
+            opt_verdict = verdict_receiver.recv() => {
+                opt_verdict
+            }
+        };
+        for j in join.iter() {
+            j.abort();
+        }
+        let opt_failure = match opt_failure {
+            Some(v) => { Some(v) }
+            None => { verdict_receiver.try_recv().ok() }
+        };
+        match opt_failure {
+            Some(v) => { Ok(v) }
+            None => { Ok(TraceVerdict::Passed { trace_index }) }
         }
-        Ok(())
     }
 
     fn handle_input_action<F>(
@@ -253,7 +336,7 @@ impl DTMServerHandler {
             let action_type = v.action_type()?;
 
             let nid = match action_type {
-                ActionType::Input => {
+                ActionType::Setup | ActionType::Check | ActionType::Input => {
                     v.dest_nid()?
                 }
                 ActionType::Internal => {
@@ -262,6 +345,9 @@ impl DTMServerHandler {
                 ActionType::Output => {
                     v.source_nid()?
                 }
+                ActionType::Fault => {
+                    v.source_nid()?
+                }
             };
             if !map.contains_key(&nid) {
                 map.insert(nid, vec![(v, index)]);
@@ -291,15 +377,19 @@ impl DTMServerHandler {
     #[async_backtrace::framed]
     async fn handle_trace(
         dtm_server_node_id: NID,
+        trace_index: u64,
         node_id: Option<NID>,
         notify: Notifier,
         trace: Vec<(ActionJson, u64)>,
         executor: Arc<ActionExecutor>,
-        sender: Arc<dyn SenderAsync<SerdeJsonString>>,
+        sender: Arc<dyn MessageToNode>,
         output_action_sequential: bool,
-        seconds_timeout: u64,
         waiter: ActionPrefixWaiter,
-        tasks: &mut Vec<JoinHandle<Option<Res<()>>>>,
+        step_controller: Option<Arc<StepController>>,
+        node_supervisor: Option<Arc<NodeSupervisor>>,
+        proxy: Option<Arc<DTMProxy>>,
+        verdict_sender: UnboundedSender<TraceVerdict>,
+        tasks: &mut Vec<JoinHandle<Option<()>>>,
     ) -> Res<()> {
         let mut seq_no_node = 0;
 
@@ -314,28 +404,47 @@ impl DTMServerHandler {
             }
             None => 0
         };
-        for (value, seq_no) in trace {
+        for (i, (value, seq_no)) in trace.iter().cloned().enumerate() {
             seq_no_node += 1;
             assert!(seq_no >= 1);
             // num start with 1
             let index = seq_no - 1;
-            let id_s;
+            let step = TraceStep::new(seq_no, node_id.map(|nid| { (nid, seq_no_node) }));
             if node_id.is_none() {
-                id_s = format!("No.{}", seq_no);
-                trace!("DTM trace, {} action: {:?}", id_s.clone(),  value);
+                trace!("DTM trace, {} action: {:?}", step,  value);
             } else {
-                id_s = format!("No.{}.{}", seq_no, seq_no_node);
-                trace!("DTM trace, Node:{:?}, {} action: {:?}", node_id, id_s.clone(),  value);
+                trace!("DTM trace, Node:{:?}, {} action: {:?}", node_id, step,  value);
+            }
+            if let Some(c) = &step_controller {
+                c.before_step(&step, &value, &trace[i + 1..], &executor).await;
             }
 
+            if let Some(millis) = VirtualClock::tick_millis(&value)? {
+                // the clock advances after all the previous actions are done
+                waiter.wait_finish_prefix(index).await;
+                let now = executor.clock().advance(value.dest_nid()?, millis);
+                trace!("node {} virtual clock advances to {}", value.dest_nid()?, now);
+                waiter.finish_one(index).await;
+                continue;
+            }
             let action_type = value.action_type()?;
+            if action_type == ActionType::Fault {
+                // a fault is enacted after all the previous actions are done
+                waiter.wait_finish_prefix(index).await;
+                let r = Self::enact_fault(&value, &executor, &node_supervisor).await;
+                if let Err(e) = r {
+                    let _ = verdict_sender.send(TraceVerdict::protocol_error(
+                        trace_index, Some(step), Some(value), e));
+                    return Ok(());
+                }
+                waiter.finish_one(index).await;
+                continue;
+            }
             let need_send_message_to_node = if
-            action_type == ActionType::Input
+            action_type.is_delivered_to_node()
             {
-                if action_type == ActionType::Input {
-                    let dest_nid = value.dest_nid()?;
-                    let _ = found_input_action.insert(dest_nid);
-                }
+                let dest_nid = value.dest_nid()?;
+                let _ = found_input_action.insert(dest_nid);
                 true
             } else {
                 if action_type == ActionType::Internal {
@@ -347,7 +456,29 @@ impl DTMServerHandler {
                 }
             };
 
-            if need_send_message_to_node {
+            // in proxy mode, the message between tested nodes is forwarded by the player
+            let opt_proxy = match &proxy {
+                Some(p) if action_type == ActionType::Input
+                    && p.is_proxied(value.source_nid()?, value.dest_nid()?) => { Some(p) }
+                _ => { None }
+            };
+            if let Some(p) = opt_proxy {
+                waiter.wait_finish_prefix(index).await;
+                let r = p.release(value.source_nid()?, value.dest_nid()?, &value.payload()?, executor.liveness()).await;
+                let opt_verdict = match r {
+                    Ok(true) => { None }
+                    Ok(false) => {
+                        Some(executor.timeout_verdict(trace_index, step.clone(), value.clone()).await)
+                    }
+                    Err(e) => {
+                        Some(TraceVerdict::protocol_error(trace_index, Some(step.clone()), Some(value.clone()), e))
+                    }
+                };
+                if let Some(v) = opt_verdict {
+                    let _ = verdict_sender.send(v);
+                    return Ok(());
+                }
+            } else if need_send_message_to_node {
                 waiter.wait_finish_prefix(index).await;
                 let value = value.serde_json_value_ref();
                 let json_value = ActionJson::from_json_value(value.clone())?;
@@ -356,47 +487,56 @@ impl DTMServerHandler {
                     json_value.message_payload_json_value()?);
                 trace!("action message: {:?}", action_message);
                 let m = Message::new(action_message, dtm_server_node_id, dest_node_id);
-                sender.send(m, OptSend::default().enable_no_wait(false))
+                let r = sender.send(m)
                     .instrument(trace_span!("message to node"))
-                    .await?;
+                    .await;
+                if let Err(e) = r {
+                    let _ = verdict_sender.send(TraceVerdict::protocol_error(
+                        trace_index, Some(step), Some(json_value), e));
+                    return Ok(());
+                }
             }
             let w = waiter.clone();
             if action_type == ActionType::Output && !output_action_sequential {
                 let e = executor.clone();
                 let n = notify.clone();
+                let vs = verdict_sender.clone();
                 let s = value.to_serde_json_string().to_string();
-                let ss = s.clone();
 
                 let f = async move {
                     let r = e.expect_node_sync(&value).instrument(trace_span!("file input")).await;
-                    match r {
-                        Ok(ok) => {
-                            w.finish_one(index).await;
-                            if !ok {
-                                error!("message timeout, expect tested node sync {} {:?}", id_s, ss);
-                                sleep(Duration::from_secs(seconds_timeout)).await;
-                                exit(-1);
-                            }
+                    w.finish_one(index).await;
+                    let opt_verdict = match r {
+                        Ok(true) => { None }
+                        Ok(false) => {
+                            Some(e.timeout_verdict(trace_index, step, value).await)
                         }
-                        Err(e) => {
-                            error!("failed error {:?}, expect action {} {}", e, id_s, ss);
-                            exit(-1);
+                        Err(err) => {
+                            Some(TraceVerdict::protocol_error(trace_index, Some(step), Some(value), err))
                         }
+                    };
+                    if let Some(v) = opt_verdict {
+                        let _ = vs.send(v);
                     }
-                    Res::Ok(())
                 };
                 let task = spawn_local_task(n, format!("wait_output_action_{}", s).as_str(), f)?;
                 tasks.push(task);
             } else {
-                let ok = executor.expect_node_sync(&value).instrument(trace_span!("file input")).await?;
-                if !ok {
-                    let s = value.to_serde_json_string().to_string();
-                    error!("message timeout, expect tested node sync {} {:?}", id_s, s);
-                    sleep(Duration::from_secs(seconds_timeout)).await;
-                    exit(-1);
-                } else {
-                    w.finish_one(index).await;
-                }
+                let r = executor.expect_node_sync(&value).instrument(trace_span!("file input")).await;
+                let verdict = match r {
+                    Ok(true) => {
+                        w.finish_one(index).await;
+                        continue;
+                    }
+                    Ok(false) => {
+                        executor.timeout_verdict(trace_index, step, value).await
+                    }
+                    Err(e) => {
+                        TraceVerdict::protocol_error(trace_index, Some(step), Some(value), e)
+                    }
+                };
+                let _ = verdict_sender.send(verdict);
+                return Ok(());
             }
         }
         // synchronize and wait for all actions done
@@ -404,17 +544,52 @@ impl DTMServerHandler {
         Ok(())
     }
 
+    /// Kill or restart a node launched by the player, or deliver a message fault to the DTM
+    /// client of the source node
+    async fn enact_fault(
+        action: &ActionJson,
+        executor: &ActionExecutor,
+        node_supervisor: &Option<Arc<NodeSupervisor>>,
+    ) -> Res<()> {
+        let fault = Fault::from_action_json(action)?;
+        debug!("enact fault {}", fault);
+        if !fault.kind.is_node_fault() {
+            executor.add_message_fault(fault).await;
+            return Ok(());
+        }
+        let supervisor = match node_supervisor {
+            Some(s) => { s.clone() }
+            None => {
+                return Err(ET::IOError(format!(
+                    "fault {} requires the player to launch the tested nodes", fault)));
+            }
+        };
+        let r = spawn_blocking(move || {
+            if fault.kind == FaultKind::Crash {
+                supervisor.kill(fault.source)
+            } else {
+                supervisor.restart(fault.source)
+            }
+        }).await;
+        match r {
+            Ok(r) => { r }
+            Err(e) => { Err(ET::RecvError(e.to_string())) }
+        }
+    }
+
     async fn handle_message_request_response(
         &self,
         endpoint_incoming: &dyn EndpointAsync<MessageControl>,
         response_receiver: &mut UnboundedReceiver<Message<MessageControl>>,
         response_sender: &UnboundedSender<Message<MessageControl>>,
+        peer: &mut Option<NID>,
         tasks: &mut Vec<JoinHandle<Option<Res<()>>>>,
     ) -> Res<()> {
         select! {
             r_req = endpoint_incoming.recv()  => {
                 match r_req {
                     Ok(m) => {
+                        *peer = Some(m.source());
                         let s = response_sender.clone();
                         let n = self.handler.notify.clone();
                         let _self = self.clone();
@@ -447,29 +622,98 @@ impl DTMServerHandler {
         Ok(())
     }
 
-    async fn handle_one_message(&self,
-                                source: NID,
-                                dest: NID,
-                                message: MessageControl,
-                                channel: UnboundedSender<Message<MessageControl>>) -> Res<()> {
+    pub(crate) async fn handle_one_message(&self,
+                                           source: NID,
+                                           dest: NID,
+                                           message: MessageControl,
+                                           channel: UnboundedSender<Message<MessageControl>>) -> Res<()> {
         match message {
             MessageControl::ActionReq { id, action, begin } => {
                 let v = action.to_serde_json_value();
                 let action_json = ActionJson::from_value(v.into_serde_json_value());
-                self.handler.executor.expect_action_in_trace(source, dest, id, action_json, begin, channel).await?;
+                if let Some(recorder) = &self.handler.recorder {
+                    recorder.record(source, &action_json, begin)?;
+                    Self::send_ack(source, dest, id, &action_json, &channel);
+                } else if let Some(monitor) = &self.handler.monitor {
+                    monitor.expect_action(&action_json, begin).await?;
+                    Self::send_ack(source, dest, id, &action_json, &channel);
+                } else {
+                    self.handler.executor.expect_action_in_trace(source, dest, id, action_json, begin, channel).await?;
+                }
+            }
+            MessageControl::ChooseReq { id, kind, candidates } => {
+                // the node makes its own choice when no trace is played
+                let value = if self.handler.recorder.is_some() || self.handler.monitor.is_some() {
+                    None
+                } else {
+                    let candidates: Vec<Value> = candidates.iter()
+                        .map(|c| { c.to_serde_json_value().into_serde_json_value() })
+                        .collect();
+                    self.handler.executor.choose(source, &kind, &candidates).await?
+                };
+                let response = Message::new(
+                    MessageControl::ChooseResp { id, value: value.map(|v| { SerdeJsonString::from_json_value(&v) }) },
+                    dest,
+                    source);
+                if let Err(e) = channel.send(response) {
+                    error!("response choice {} , error : {}" , kind, e.to_string())
+                }
             }
-            MessageControl::ActionACK { id: _ } => {
+            MessageControl::ClockReq { id, wait_until } => {
+                // the node uses the real clock when no trace is played
+                let now = if self.handler.recorder.is_some() || self.handler.monitor.is_some() {
+                    None
+                } else {
+                    let clock = self.handler.executor.clock();
+                    let now = match wait_until {
+                        Some(deadline) => {
+                            clock.wait_until(source, deadline, self.handler.executor.liveness()).await
+                        }
+                        None => { clock.now(source) }
+                    };
+                    Some(now)
+                };
+                let response = Message::new(MessageControl::ClockResp { id, now }, dest, source);
+                if let Err(e) = channel.send(response) {
+                    error!("response clock, error : {}" , e.to_string())
+                }
+            }
+            MessageControl::ActionACK { .. }
+            | MessageControl::ChooseResp { .. }
+            | MessageControl::ClockResp { .. } => {
                 panic!("error message")
             }
+            MessageControl::Heartbeat { paused } => {
+                self.handler.executor.liveness().heartbeat(source, paused);
+            }
         }
         Ok(())
     }
 
+    // ACK of an action accepted without the trace
+    fn send_ack(
+        source: NID,
+        dest: NID,
+        id: String,
+        action: &ActionJson,
+        channel: &UnboundedSender<Message<MessageControl>>,
+    ) {
+        let response = Message::new(
+            MessageControl::ActionACK { id, faults: vec![] },
+            dest,
+            source);
+        if let Err(e) = channel.send(response) {
+            error!("response action {:?} , error : {}" , action, e.to_string())
+        }
+    }
+
     async fn message_loop(&self, endpoint_incoming: &dyn EndpointAsync<MessageControl>) -> Res<()> {
         let mut tasks = vec![];
+        let mut peer = None;
         let (s, mut r) = mpsc::unbounded_channel();
         let ret = loop {
-            let r = self.handle_message_request_response(endpoint_incoming, &mut r, &s, &mut tasks).await;
+            let r = self.handle_message_request_response(
+                endpoint_incoming, &mut r, &s, &mut peer, &mut tasks).await;
             match r {
                 Ok(()) => {}
                 Err(e) => {
@@ -477,6 +721,10 @@ impl DTMServerHandler {
                 }
             };
         };
+        if let Some(nid) = peer {
+            // the time waiting a disconnected node counts to timeout
+            self.handler.executor.liveness().disconnected(nid);
+        }
         for task in tasks {
             let _ = task.await;
         }
@@ -488,7 +736,6 @@ impl Clone for DTMServerHandler {
     fn clone(&self) -> Self {
         Self {
             handler: self.handler.clone(),
-            seconds_timeout: self.seconds_timeout,
             node_id: self.node_id,
         }
     }
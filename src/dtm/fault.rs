@@ -0,0 +1,188 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use bincode::{Decode, Encode};
+use scupt_util::error_type::ET;
+use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+use serde::{Deserialize, Serialize};
+
+use crate::action::action_json::ActionJson;
+
+/// The kind of fault, which is the message name of a Fault action, for example:
+/// ```tla
+/// [
+///     p |-> [source |-> A_n1, dest |-> A_n2, name |-> "Drop"],
+///     t |-> "F"
+/// ]
+/// ```
+#[derive(
+Copy,
+Clone,
+Serialize,
+Hash,
+PartialEq,
+Eq,
+Debug,
+Deserialize,
+Decode,
+Encode,
+)]
+pub enum FaultKind {
+    /// kill the node `source`(the same as `dest`)
+    Crash,
+    /// restart the node `source`(the same as `dest`) killed before
+    Restart,
+    /// the node `source` drops all its messages to `dest` until a Heal fault
+    Partition,
+    /// heal the partition from `source` to `dest`
+    Heal,
+    /// the node `source` drops its next message to `dest`
+    Drop,
+    /// the node `source` sends its next message to `dest` twice
+    Duplicate,
+}
+
+/// A fault the deterministic player enacts
+#[derive(
+Clone,
+Serialize,
+Hash,
+PartialEq,
+Eq,
+Debug,
+Deserialize,
+Decode,
+Encode,
+)]
+pub struct Fault {
+    pub kind: FaultKind,
+    pub source: NID,
+    pub dest: NID,
+}
+
+impl FaultKind {
+    pub fn from_name(name: &str) -> Res<Self> {
+        let kind = match name {
+            "Crash" => { FaultKind::Crash }
+            "Restart" => { FaultKind::Restart }
+            "Partition" => { FaultKind::Partition }
+            "Heal" => { FaultKind::Heal }
+            "Drop" => { FaultKind::Drop }
+            "Duplicate" => { FaultKind::Duplicate }
+            _ => { return Err(ET::ParseError(format!("unknown fault {}", name))); }
+        };
+        Ok(kind)
+    }
+
+    /// A fault on a node process, which is enacted by the player, otherwise, a fault on
+    /// messages, which is enacted by the DTM client of the source node
+    pub fn is_node_fault(&self) -> bool {
+        matches!(self, FaultKind::Crash | FaultKind::Restart)
+    }
+}
+
+impl Fault {
+    pub fn from_action_json(action: &ActionJson) -> Res<Self> {
+        let name = action.action_name()?;
+        let kind_name = name.split("::").next().unwrap_or_default();
+        Ok(Self {
+            kind: FaultKind::from_name(kind_name)?,
+            source: action.source_nid()?,
+            dest: action.dest_nid()?,
+        })
+    }
+}
+
+/// The message faults delivered by the player to a tested node, which are enacted by the node
+#[derive(Default)]
+pub struct MessageFaults {
+    faults: Mutex<Vec<Fault>>,
+}
+
+impl MessageFaults {
+    pub fn add(&self, faults: &[Fault]) {
+        let mut vec = self.faults.lock().unwrap();
+        for f in faults {
+            match f.kind {
+                FaultKind::Heal => {
+                    vec.retain(|p| {
+                        !(p.kind == FaultKind::Partition && p.dest == f.dest)
+                    });
+                }
+                FaultKind::Partition | FaultKind::Drop | FaultKind::Duplicate => {
+                    vec.push(f.clone());
+                }
+                FaultKind::Crash | FaultKind::Restart => {}
+            }
+        }
+    }
+
+    /// The fault on the next message the tested node sends to `dest`, which is `Drop`,
+    /// `Duplicate`, or None when the message is sent as usual.
+    /// A `Drop` or `Duplicate` fault applies to one message, a `Partition` fault makes all
+    /// messages to `dest` be dropped until a `Heal` fault.
+    pub fn next_fault(&self, dest: NID) -> Option<FaultKind> {
+        let mut faults = self.faults.lock().unwrap();
+        let pos = faults.iter().position(|f| { f.dest == dest })?;
+        let kind = faults[pos].kind;
+        match kind {
+            FaultKind::Partition => { Some(FaultKind::Drop) }
+            _ => {
+                faults.remove(pos);
+                Some(kind)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}->{}", self.kind, self.source, self.dest)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::action::action_json::ActionJson;
+    use crate::action::action_type::ActionType;
+    use crate::action::tla_actions::TLAAction;
+    use crate::dtm::fault::{Fault, FaultKind, MessageFaults};
+
+    #[test]
+    fn test_fault() {
+        let tla_action = json!({
+            "t": "F",
+            "p": {"source": 1, "dest": 2, "name": "Duplicate"}
+        });
+        let action = TLAAction::from(&tla_action).unwrap().to_action_json().unwrap();
+        assert_eq!(action.action_type().unwrap(), ActionType::Fault);
+        let fault = Fault::from_action_json(&action).unwrap();
+        assert_eq!(fault, Fault { kind: FaultKind::Duplicate, source: 1, dest: 2 });
+        assert!(!fault.kind.is_node_fault());
+
+        let action = ActionJson::from_value(json!({
+            "Fault": {"source": 3, "dest": 3, "payload": {"Crash": {"reason": 1}}}
+        }));
+        assert_eq!(Fault::from_action_json(&action).unwrap().kind, FaultKind::Crash);
+
+        let action = ActionJson::from_value(json!({
+            "Fault": {"source": 3, "dest": 3, "payload": "Lost"}
+        }));
+        assert!(Fault::from_action_json(&action).is_err());
+
+        let faults = MessageFaults::default();
+        faults.add(&[
+            Fault { kind: FaultKind::Partition, source: 1, dest: 2 },
+            Fault { kind: FaultKind::Duplicate, source: 1, dest: 3 },
+        ]);
+        assert_eq!(faults.next_fault(3), Some(FaultKind::Duplicate));
+        assert_eq!(faults.next_fault(3), None);
+        assert_eq!(faults.next_fault(2), Some(FaultKind::Drop));
+        assert_eq!(faults.next_fault(2), Some(FaultKind::Drop));
+        faults.add(&[Fault { kind: FaultKind::Heal, source: 1, dest: 2 }]);
+        assert_eq!(faults.next_fault(2), None);
+    }
+}
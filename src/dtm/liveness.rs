@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::future::pending;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use scupt_util::node_id::NID;
+use tokio::time::{Duration, Instant, sleep};
+
+/// The interval a DTM client sends heartbeat to the player
+pub const HEARTBEAT_INTERVAL_MILLIS: u64 = 1000;
+
+/// A client is considered paused(for example, stopped at a breakpoint) when the player does not
+/// receive any heartbeat from it in this time
+const HEARTBEAT_STALE_MILLIS: u64 = 3 * HEARTBEAT_INTERVAL_MILLIS;
+
+/// The interval to check the liveness of the clients while waiting
+const CHECK_INTERVAL_MILLIS: u64 = 100;
+
+struct ClientState {
+    last_heartbeat: Instant,
+    paused: bool,
+    disconnected: bool,
+}
+
+/// Track the liveness of the DTM clients, timeout only counts the time during which all
+/// clients are alive and not paused.
+///
+/// A client which has never sent a heartbeat is not tracked, and a disconnected client does
+/// not stop the counting, so a crashed node still causes a timeout.
+#[derive(Clone)]
+pub struct Liveness {
+    seconds_timeout: u64,
+    debug_mode: bool,
+    // the player itself holds the test, for example, stopped by a step command
+    held: Arc<AtomicBool>,
+    clients: Arc<Mutex<HashMap<NID, ClientState>>>,
+}
+
+impl Liveness {
+    /// In `debug_mode`, timeout is suspended and the player waits as long as it takes
+    pub fn new(seconds_timeout: u64, debug_mode: bool) -> Self {
+        Self {
+            seconds_timeout,
+            debug_mode,
+            held: Default::default(),
+            clients: Default::default(),
+        }
+    }
+
+    pub fn heartbeat(&self, node_id: NID, paused: bool) {
+        let mut clients = self.clients.lock().unwrap();
+        let _ = clients.insert(node_id, ClientState {
+            last_heartbeat: Instant::now(),
+            paused,
+            disconnected: false,
+        });
+    }
+
+    /// Timeout is not counted while the player holds the test
+    pub fn hold(&self, held: bool) {
+        self.held.store(held, Ordering::SeqCst);
+    }
+
+    pub fn disconnected(&self, node_id: NID) {
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(state) = clients.get_mut(&node_id) {
+            state.disconnected = true;
+        }
+    }
+
+    /// Return after the clients are alive for `seconds_timeout` seconds in total,
+    /// never return in debug mode
+    pub async fn wait_timeout(&self) {
+        if self.debug_mode {
+            pending::<()>().await;
+        }
+        let timeout = Duration::from_secs(self.seconds_timeout);
+        let mut elapsed = Duration::ZERO;
+        let mut last = Instant::now();
+        while elapsed < timeout {
+            let check = Duration::from_millis(CHECK_INTERVAL_MILLIS);
+            sleep(check.min(timeout - elapsed)).await;
+            let now = Instant::now();
+            if self.is_alive(now) {
+                elapsed += now - last;
+            }
+            last = now;
+        }
+    }
+
+    fn is_alive(&self, now: Instant) -> bool {
+        if self.held.load(Ordering::SeqCst) {
+            return false;
+        }
+        let clients = self.clients.lock().unwrap();
+        let stale = Duration::from_millis(HEARTBEAT_STALE_MILLIS);
+        clients.values().all(|s| {
+            s.disconnected ||
+                (!s.paused && now.duration_since(s.last_heartbeat) <= stale)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::time::{Duration, Instant};
+
+    use crate::dtm::liveness::Liveness;
+
+    #[test]
+    fn test_liveness() {
+        let liveness = Liveness::new(1, false);
+        assert!(liveness.is_alive(Instant::now()));
+
+        liveness.heartbeat(1, true);
+        assert!(!liveness.is_alive(Instant::now()));
+        liveness.heartbeat(1, false);
+        assert!(liveness.is_alive(Instant::now()));
+        assert!(!liveness.is_alive(Instant::now() + Duration::from_secs(10)));
+        liveness.disconnected(1);
+        assert!(liveness.is_alive(Instant::now() + Duration::from_secs(10)));
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use scupt_net::message_sender_async::SenderAsync;
+use scupt_net::opt_send::OptSend;
+use scupt_util::message::Message;
+use scupt_util::res::Res;
+use scupt_util::serde_json_string::SerdeJsonString;
+
+/// Deliver the messages of the Input actions of a trace from the player to the tested nodes
+#[async_trait]
+pub trait MessageToNode: Sync + Send {
+    async fn send(&self, message: Message<SerdeJsonString>) -> Res<()>;
+}
+
+/// Deliver the messages over the network, by a node connected to the tested nodes
+pub struct MessageToNodeNet {
+    sender: Arc<dyn SenderAsync<SerdeJsonString>>,
+}
+
+impl MessageToNodeNet {
+    pub fn new(sender: Arc<dyn SenderAsync<SerdeJsonString>>) -> Self {
+        Self {
+            sender
+        }
+    }
+}
+
+#[async_trait]
+impl MessageToNode for MessageToNodeNet {
+    async fn send(&self, message: Message<SerdeJsonString>) -> Res<()> {
+        self.sender.send(message, OptSend::default().enable_no_wait(false)).await?;
+        Ok(())
+    }
+}
@@ -17,9 +17,23 @@ mod dtm_server_handler;
 mod async_action_driver_impl;
 mod action_input_json;
 pub mod action_driver;
+pub mod trace_verdict;
+pub mod divergence_report;
 mod test_dtm_player;
 
 mod action_waiter;
+mod liveness;
+pub mod step_control;
+pub mod node_supervisor;
+pub mod fault;
+pub mod dtm_proxy;
+pub mod spec_monitor;
+pub mod action_recorder;
+pub mod dtm_in_process;
+pub mod state_projection;
+mod message_to_node;
+mod choice;
+mod virtual_clock;
 mod sync_action_driver;
 mod sync_action_driver_impl;
 
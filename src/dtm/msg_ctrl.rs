@@ -1,11 +1,14 @@
 use bincode::Decode;
 use bincode::Encode;
+use scupt_util::error_type::ET;
 use scupt_util::message::MsgTrait;
 use scupt_util::res::Res;
 use scupt_util::serde_json_string::SerdeJsonString;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::dtm::fault::Fault;
+
 #[derive(
 Clone,
 Serialize,
@@ -26,7 +29,39 @@ pub enum MessageControl {
     },
     ActionACK {
         // a UUID
-        id: String
+        id: String,
+        // the message faults the DTM client of the node enacts from now on
+        faults: Vec<Fault>,
+    },
+    Heartbeat {
+        // the tested node reports it is paused, for example, by a debugger
+        paused: bool
+    },
+    ChooseReq {
+        // a UUID let the DTM client to retrieve the response ChooseResp message
+        id: String,
+        // the kind of the choice, which is a key in the payload of an Internal action
+        kind: String,
+        // the values in JSON the node can choose from, any value when it is empty
+        candidates: Vec<SerdeJsonString>,
+    },
+    ChooseResp {
+        // a UUID
+        id: String,
+        // the value in JSON picked by the trace, None if the player picks no value
+        value: Option<SerdeJsonString>,
+    },
+    ClockReq {
+        // a UUID
+        id: String,
+        // respond after the virtual clock of the node reaches it, or respond at once if None
+        wait_until: Option<u64>,
+    },
+    ClockResp {
+        // a UUID
+        id: String,
+        // the virtual time in milliseconds, None if the player does not drive the clock
+        now: Option<u64>,
     },
 }
 
@@ -39,9 +74,24 @@ impl MessageControl {
             MessageControl::ActionReq { id, .. } => {
                 Ok(id.clone())
             }
-            MessageControl::ActionACK { id } => {
+            MessageControl::ActionACK { id, .. } => {
+                Ok(id.clone())
+            }
+            MessageControl::ChooseReq { id, .. } => {
+                Ok(id.clone())
+            }
+            MessageControl::ChooseResp { id, .. } => {
                 Ok(id.clone())
             }
+            MessageControl::ClockReq { id, .. } => {
+                Ok(id.clone())
+            }
+            MessageControl::ClockResp { id, .. } => {
+                Ok(id.clone())
+            }
+            MessageControl::Heartbeat { .. } => {
+                Err(ET::NoneOption)
+            }
         }
     }
 }
\ No newline at end of file
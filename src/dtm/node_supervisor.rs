@@ -0,0 +1,313 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use scupt_util::error_type::ET;
+use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+use scupt_util::res_of::{res_io, res_parse};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+/// How to know a launched node is ready to run a trace
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReadinessProbe {
+    /// ready when the address(a template) accepts TCP connection
+    Tcp {
+        addr: String,
+        #[serde(default = "default_seconds_ready_timeout")]
+        seconds_timeout: u64,
+    },
+    /// ready after a fixed delay
+    Delay {
+        millis: u64,
+    },
+}
+
+/// The launch command of a tested node.
+///
+/// `argv`, the values of `env` and `cwd` are templates, in which `{nid}`, `{addr}`,
+/// `{player_nid}`, `{player_addr}`, `{trace_id}` and `{trace_index}` are replaced.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct NodeLaunch {
+    pub nid: NID,
+    pub argv: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub ready: Option<ReadinessProbe>,
+}
+
+/// The values the launch templates are filled with for a trace
+#[derive(Clone, Debug)]
+pub struct LaunchContext {
+    pub trace_id: String,
+    pub trace_index: u64,
+    pub player_nid: NID,
+    pub player_addr: SocketAddr,
+    /// the address of each tested node
+    pub peers: HashMap<NID, SocketAddr>,
+    /// the stdout/stderr of the nodes are written to `<log_dir>/<trace_id>/node_<nid>.{stdout,stderr}.log`
+    pub log_dir: String,
+}
+
+struct SupervisedNode {
+    nid: NID,
+    child: Child,
+}
+
+/// Launch the tested nodes before a trace, watch them during the trace and reap them after
+pub struct NodeSupervisor {
+    launches: Vec<NodeLaunch>,
+    context: LaunchContext,
+    log_dir: PathBuf,
+    nodes: Mutex<Vec<SupervisedNode>>,
+    // the nodes killed by Crash faults, whose exit is not a crash
+    killed: Mutex<HashSet<NID>>,
+    crashed: Mutex<Option<(NID, String)>>,
+}
+
+fn default_seconds_ready_timeout() -> u64 {
+    30
+}
+
+impl LaunchContext {
+    fn fill(&self, nid: NID, template: &str) -> String {
+        let addr = self.peers.get(&nid).map_or(String::new(), |a| { a.to_string() });
+        template
+            .replace("{nid}", nid.to_string().as_str())
+            .replace("{addr}", addr.as_str())
+            .replace("{player_nid}", self.player_nid.to_string().as_str())
+            .replace("{player_addr}", self.player_addr.to_string().as_str())
+            .replace("{trace_id}", self.trace_id.as_str())
+            .replace("{trace_index}", self.trace_index.to_string().as_str())
+    }
+}
+
+impl NodeSupervisor {
+    /// Spawn the nodes and wait until they are ready.
+    /// The nodes launched are killed if any node cannot start. A node crashed before it is ready
+    /// is not an error here, but reported by `poll_crashed`.
+    pub fn launch(launches: &[NodeLaunch], context: &LaunchContext) -> Res<Self> {
+        let log_dir = PathBuf::from(context.log_dir.clone()).join(context.trace_id.clone());
+        res_io(create_dir_all(log_dir.clone()))?;
+        let supervisor = Self {
+            launches: launches.to_vec(),
+            context: context.clone(),
+            log_dir: log_dir.clone(),
+            nodes: Mutex::new(vec![]),
+            killed: Mutex::new(HashSet::new()),
+            crashed: Mutex::new(None),
+        };
+        for launch in launches {
+            let r = Self::spawn(launch, context, &log_dir, false);
+            match r {
+                Ok(child) => {
+                    supervisor.nodes.lock().unwrap().push(SupervisedNode { nid: launch.nid, child });
+                }
+                Err(e) => {
+                    supervisor.shutdown();
+                    return Err(e);
+                }
+            }
+        }
+        for launch in launches {
+            if supervisor.poll_crashed().is_some() {
+                break;
+            }
+            let r = supervisor.wait_ready(launch, context);
+            if let Err(e) = r {
+                supervisor.shutdown();
+                return Err(e);
+            }
+        }
+        Ok(supervisor)
+    }
+
+    fn spawn(launch: &NodeLaunch, context: &LaunchContext, log_dir: &Path, append_log: bool) -> Res<Child> {
+        let argv: Vec<String> = launch.argv.iter().map(|s| { context.fill(launch.nid, s) }).collect();
+        if argv.is_empty() {
+            return Err(ET::IOError(format!("empty launch command of node {}", launch.nid)));
+        }
+        let open_log = |name: String| -> Res<File> {
+            let path = log_dir.join(name);
+            if append_log {
+                res_io(OpenOptions::new().create(true).append(true).open(path))
+            } else {
+                res_io(File::create(path))
+            }
+        };
+        let stdout = open_log(format!("node_{}.stdout.log", launch.nid))?;
+        let stderr = open_log(format!("node_{}.stderr.log", launch.nid))?;
+        let mut command = Command::new(argv[0].clone());
+        command.args(&argv[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::from(stdout))
+            .stderr(Stdio::from(stderr));
+        for (k, v) in launch.env.iter() {
+            command.env(k, context.fill(launch.nid, v));
+        }
+        if let Some(cwd) = &launch.cwd {
+            command.current_dir(context.fill(launch.nid, cwd));
+        }
+        debug!("launch node {}, {:?}", launch.nid, argv);
+        res_io(command.spawn())
+    }
+
+    /// Wait until the node is ready, or any node crashed, which is reported by `poll_crashed`
+    fn wait_ready(&self, launch: &NodeLaunch, context: &LaunchContext) -> Res<()> {
+        match &launch.ready {
+            None => {}
+            Some(ReadinessProbe::Delay { millis }) => {
+                sleep(Duration::from_millis(*millis));
+            }
+            Some(ReadinessProbe::Tcp { addr, seconds_timeout }) => {
+                let addr: SocketAddr = res_parse(context.fill(launch.nid, addr).parse())?;
+                let begin = Instant::now();
+                while TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_err() {
+                    if self.poll_crashed().is_some() {
+                        break;
+                    }
+                    if begin.elapsed() > Duration::from_secs(*seconds_timeout) {
+                        return Err(ET::IOError(format!("node {} is not ready at {}", launch.nid, addr)));
+                    }
+                    sleep(Duration::from_millis(100));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if any node exit with failure, return the node id and the exit status
+    pub fn poll_crashed(&self) -> Option<(NID, String)> {
+        let mut crashed = self.crashed.lock().unwrap();
+        if crashed.is_none() {
+            let mut nodes = self.nodes.lock().unwrap();
+            let killed = self.killed.lock().unwrap();
+            for n in nodes.iter_mut() {
+                if killed.contains(&n.nid) {
+                    continue;
+                }
+                if let Ok(Some(status)) = n.child.try_wait() {
+                    if !status.success() {
+                        error!("node {} crashed, {}", n.nid, status);
+                        *crashed = Some((n.nid, status.to_string()));
+                        break;
+                    }
+                }
+            }
+        }
+        crashed.clone()
+    }
+
+    /// Kill the node `nid` for a Crash fault
+    pub fn kill(&self, nid: NID) -> Res<()> {
+        self.killed.lock().unwrap().insert(nid);
+        let mut nodes = self.nodes.lock().unwrap();
+        let n = match nodes.iter_mut().find(|n| { n.nid == nid }) {
+            Some(n) => { n }
+            None => { return Err(ET::IOError(format!("node {} is not launched by the player", nid))); }
+        };
+        if let Ok(None) = n.child.try_wait() {
+            res_io(n.child.kill())?;
+        }
+        let _ = n.child.wait();
+        Ok(())
+    }
+
+    /// Launch the node `nid` again for a Restart fault, the node is killed first if it is
+    /// running. The output of the node is appended to its log files.
+    pub fn restart(&self, nid: NID) -> Res<()> {
+        let launch = match self.launches.iter().find(|l| { l.nid == nid }) {
+            Some(l) => { l }
+            None => { return Err(ET::IOError(format!("node {} is not launched by the player", nid))); }
+        };
+        self.kill(nid)?;
+        let child = Self::spawn(launch, &self.context, &self.log_dir, true)?;
+        {
+            let mut nodes = self.nodes.lock().unwrap();
+            nodes.retain(|n| { n.nid != nid });
+            nodes.push(SupervisedNode { nid, child });
+        }
+        self.killed.lock().unwrap().remove(&nid);
+        self.wait_ready(launch, &self.context)
+    }
+
+    /// Kill the running nodes and reap all of them
+    pub fn shutdown(&self) {
+        let mut nodes = self.nodes.lock().unwrap();
+        for n in nodes.iter_mut() {
+            if let Ok(None) = n.child.try_wait() {
+                let _ = n.child.kill();
+            }
+            let _ = n.child.wait();
+        }
+        nodes.clear();
+    }
+}
+
+impl Drop for NodeSupervisor {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    use crate::dtm::node_supervisor::{LaunchContext, NodeLaunch, NodeSupervisor, ReadinessProbe};
+
+    #[test]
+    fn test_node_supervisor() {
+        let context = LaunchContext {
+            trace_id: "trace_1".to_string(),
+            trace_index: 1,
+            player_nid: 1,
+            player_addr: "127.0.0.1:8000".parse().unwrap(),
+            peers: HashMap::new(),
+            log_dir: std::env::temp_dir().join(format!("test_node_supervisor_{}", std::process::id()))
+                .to_str().unwrap().to_string(),
+        };
+        let launch = |nid, script: &str| {
+            NodeLaunch {
+                nid,
+                argv: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+                env: HashMap::from([("TRACE".to_string(), "{trace_id}".to_string())]),
+                cwd: None,
+                ready: Some(ReadinessProbe::Delay { millis: 10 }),
+            }
+        };
+        let supervisor = NodeSupervisor::launch(
+            &[launch(2, "echo $TRACE; sleep 10"), launch(3, "sleep 0.1; exit 3")],
+            &context).unwrap();
+        assert!(supervisor.poll_crashed().is_none());
+        sleep(Duration::from_millis(500));
+        let (nid, _) = supervisor.poll_crashed().unwrap();
+        assert_eq!(nid, 3);
+        supervisor.shutdown();
+
+        // a node killed by a Crash fault is not a crash
+        let supervisor = NodeSupervisor::launch(&[launch(4, "sleep 10")], &context).unwrap();
+        supervisor.kill(4).unwrap();
+        assert!(supervisor.poll_crashed().is_none());
+        supervisor.restart(4).unwrap();
+        assert!(supervisor.poll_crashed().is_none());
+        assert!(supervisor.kill(5).is_err());
+        supervisor.shutdown();
+
+        let log = std::path::PathBuf::from(context.log_dir.clone()).join("trace_1").join("node_2.stdout.log");
+        assert_eq!(std::fs::read_to_string(log).unwrap().trim(), "trace_1");
+        let _ = std::fs::remove_dir_all(context.log_dir);
+    }
+}
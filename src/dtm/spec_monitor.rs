@@ -0,0 +1,231 @@
+use std::sync::{Arc, Mutex};
+
+use scupt_util::res::Res;
+use tokio::select;
+use tokio::sync::Notify;
+use tracing::{error, trace};
+
+use crate::action::action_json::ActionJson;
+use crate::dtm::divergence_report::{DivergenceReport, json_diff, QueuePending};
+use crate::dtm::liveness::Liveness;
+use crate::dtm::trace_verdict::{TraceStep, TraceVerdict};
+use crate::trace::spec_graph::{SpecCursor, SpecGraph};
+
+struct MonitorState {
+    cursor: SpecCursor,
+    // the number of actions accepted
+    accepted: u64,
+    // the actions requested by the tested nodes, which match no edge of the current states
+    blocked: Vec<ActionJson>,
+}
+
+/// Online conformance monitor, which checks the actions of the tested nodes against the whole
+/// state graph of the specification rather than a fixed trace.
+///
+/// An action requested by a node is acknowledged once it is an outgoing edge of the current
+/// states, so the tested nodes can run any workload.
+pub struct SpecMonitor {
+    liveness: Liveness,
+    state: Mutex<MonitorState>,
+    progress: Notify,
+}
+
+impl SpecMonitor {
+    pub fn new(graph: Arc<SpecGraph>, liveness: Liveness) -> Self {
+        Self {
+            liveness,
+            state: Mutex::new(MonitorState {
+                cursor: SpecCursor::new(graph),
+                accepted: 0,
+                blocked: vec![],
+            }),
+            progress: Notify::new(),
+        }
+    }
+
+    /// Wait until `action` is accepted by the specification.
+    /// An action is checked at the point the player synchronizes on it: the begin of an Output
+    /// or Internal action, and the end of a Setup, Check or Input action. The other notification
+    /// of an action is accepted at once.
+    pub async fn expect_action(&self, action: &ActionJson, begin: bool) -> Res<()> {
+        if begin == action.action_type()?.is_delivered_to_node() {
+            return Ok(());
+        }
+        let mut blocked = false;
+        loop {
+            let notified = self.progress.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.cursor.step(action) {
+                    state.accepted += 1;
+                    if blocked {
+                        Self::remove_blocked(&mut state.blocked, action);
+                    }
+                    trace!("MONITOR: accept action No.{} {:?}", state.accepted, action);
+                    self.progress.notify_waiters();
+                    return Ok(());
+                }
+                if !blocked {
+                    trace!("MONITOR: action {:?} matches no edge, wait", action);
+                    state.blocked.push(action.clone());
+                    blocked = true;
+                }
+            }
+            // an action performed by other nodes may enable it
+            notified.await;
+        }
+    }
+
+    /// Wait until the run ends.
+    /// The run passed if the specification reached a final state, or no node requested any
+    /// action before timeout.
+    /// The run diverged if some actions requested match no edge before timeout.
+    pub async fn verdict(&self, trace_index: u64) -> TraceVerdict {
+        loop {
+            let notified = self.progress.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.state.lock().unwrap().cursor.is_final() {
+                return TraceVerdict::Passed { trace_index };
+            }
+            select! {
+                _ = notified => {}
+                _ = self.liveness.wait_timeout() => {
+                    let state = self.state.lock().unwrap();
+                    return if state.blocked.is_empty() {
+                        trace!("MONITOR: no action in progress, {} actions accepted", state.accepted);
+                        TraceVerdict::Passed { trace_index }
+                    } else {
+                        let verdict = Self::diverged_verdict(trace_index, &state);
+                        error!("{}", verdict);
+                        verdict
+                    };
+                }
+            }
+        }
+    }
+
+    fn diverged_verdict(trace_index: u64, state: &MonitorState) -> TraceVerdict {
+        let enabled = state.cursor.enabled();
+        // the enabled action closest to the first blocked one
+        let expected = enabled.iter().min_by_key(|a| {
+            json_diff(a.serde_json_value_ref(), state.blocked[0].serde_json_value_ref()).len()
+        }).cloned().unwrap_or_else(|| { state.blocked[0].clone() });
+        let pending = vec![QueuePending {
+            queue: "spec_enabled".to_string(),
+            queued: vec![],
+            waiting: enabled,
+        }];
+        TraceVerdict::Diverged {
+            trace_index,
+            step: TraceStep::new(state.accepted + 1, None),
+            expected: expected.clone(),
+            report: DivergenceReport::new(expected, pending, state.blocked.clone()),
+        }
+    }
+
+    fn remove_blocked(blocked: &mut Vec<ActionJson>, action: &ActionJson) {
+        if let Some(i) = blocked.iter().position(|a| { a == action }) {
+            blocked.remove(i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use tokio::runtime::Builder;
+    use tokio::task::LocalSet;
+
+    use crate::action::action_json::ActionJson;
+    use crate::dtm::liveness::Liveness;
+    use crate::dtm::spec_monitor::SpecMonitor;
+    use crate::dtm::trace_verdict::TraceVerdict;
+    use crate::trace::spec_graph::SpecGraph;
+
+    fn action(name: &str) -> ActionJson {
+        ActionJson::from_value(json!({
+            "Internal": {"source": 1, "dest": 1, "payload": {name: 1}}
+        }))
+    }
+
+    fn input(name: &str) -> ActionJson {
+        ActionJson::from_value(json!({
+            "Input": {"source": 2, "dest": 1, "payload": {name: 1}}
+        }))
+    }
+
+    #[test]
+    fn test_spec_monitor_input() {
+        // 1 -a-> 2 -i-> 3 -b-> 4
+        let graph = Arc::new(SpecGraph::new(vec![
+            (1, 0, vec![action("a")]),
+            (2, 1, vec![input("i")]),
+            (3, 2, vec![action("b")]),
+        ]));
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let ls = LocalSet::new();
+        ls.block_on(&runtime, async {
+            let monitor = Arc::new(SpecMonitor::new(graph, Liveness::new(1, false)));
+            monitor.expect_action(&action("a"), true).await.unwrap();
+            // the begin of an Input action does not step the specification
+            monitor.expect_action(&input("i"), true).await.unwrap();
+            let m = monitor.clone();
+            // b waits until the Input action ends
+            let task_b = tokio::task::spawn_local(async move {
+                m.expect_action(&action("b"), true).await.unwrap();
+            });
+            tokio::task::yield_now().await;
+            assert!(!task_b.is_finished());
+            monitor.expect_action(&input("i"), false).await.unwrap();
+            task_b.await.unwrap();
+            assert!(monitor.verdict(1).await.is_passed());
+        });
+    }
+
+    #[test]
+    fn test_spec_monitor() {
+        // 1 -a-> 2 -b-> 3 -c-> 4
+        let graph = Arc::new(SpecGraph::new(vec![
+            (1, 0, vec![action("a")]),
+            (2, 1, vec![action("b")]),
+            (3, 2, vec![action("c")]),
+        ]));
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let ls = LocalSet::new();
+        ls.block_on(&runtime, async {
+            let monitor = Arc::new(SpecMonitor::new(graph.clone(), Liveness::new(1, false)));
+            let m = monitor.clone();
+            // b is requested before a, and waits until a is accepted
+            let task_b = tokio::task::spawn_local(async move {
+                m.expect_action(&action("b"), true).await.unwrap();
+            });
+            tokio::task::yield_now().await;
+            monitor.expect_action(&action("a"), true).await.unwrap();
+            task_b.await.unwrap();
+            monitor.expect_action(&action("c"), false).await.unwrap();
+            monitor.expect_action(&action("c"), true).await.unwrap();
+            assert!(monitor.verdict(1).await.is_passed());
+
+            let monitor = Arc::new(SpecMonitor::new(graph, Liveness::new(1, false)));
+            monitor.expect_action(&action("a"), true).await.unwrap();
+            let m = monitor.clone();
+            let _task = tokio::task::spawn_local(async move {
+                m.expect_action(&action("c"), true).await.unwrap();
+            });
+            let verdict = monitor.verdict(2).await;
+            match &verdict {
+                TraceVerdict::Diverged { step, expected, report, .. } => {
+                    assert_eq!(step.seq_no, 2);
+                    assert_eq!(*expected, action("b"));
+                    assert_eq!(report.unmatched, vec![action("c")]);
+                }
+                _ => { panic!("expect diverged, {}", verdict) }
+            }
+        });
+    }
+}
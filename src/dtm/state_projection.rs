@@ -0,0 +1,65 @@
+use scupt_util::error_type::ET;
+use scupt_util::res::Res;
+use serde_json::{Map, Value};
+
+/// Project a state to the part compared by a Check action.
+///
+/// The player compares the projection of the state expected by the specification, with the
+/// projection of the state the tested node observes, which are the payloads of the Check actions
+/// in the trace and reported by the node.
+pub trait StateProjection: Sync + Send {
+    fn project(&self, state: &Value) -> Res<Value>;
+}
+
+/// Keep the values at some JSON pointers of a state, such as `/Raft/term`, the other parts of
+/// the state are ignored
+pub struct FieldProjection {
+    pointers: Vec<String>,
+}
+
+impl FieldProjection {
+    pub fn new(pointers: Vec<String>) -> Self {
+        Self {
+            pointers
+        }
+    }
+}
+
+impl StateProjection for FieldProjection {
+    fn project(&self, state: &Value) -> Res<Value> {
+        let mut map = Map::new();
+        for p in self.pointers.iter() {
+            let v = match state.pointer(p) {
+                Some(v) => { v.clone() }
+                None => {
+                    return Err(ET::ParseError(format!("state field {} not found", p)));
+                }
+            };
+            let _ = map.insert(p.clone(), v);
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use scupt_util::error_type::ET;
+    use serde_json::json;
+
+    use crate::dtm::state_projection::{FieldProjection, StateProjection};
+
+    #[test]
+    fn test_field_projection() {
+        let projection = FieldProjection::new(vec!["/State/term".to_string(), "/State/log".to_string()]);
+        let expected = json!({"State": {"term": 2, "log": [1, 2], "commit_index": 1}});
+        let observed = json!({"State": {"term": 2, "log": [1, 2], "commit_index": 2}});
+        assert_eq!(projection.project(&expected).unwrap(), projection.project(&observed).unwrap());
+        assert_eq!(
+            projection.project(&expected).unwrap(),
+            json!({"/State/term": 2, "/State/log": [1, 2]}));
+        match projection.project(&json!({"State": {"term": 2}})) {
+            Err(ET::ParseError(m)) => { assert_eq!(m, "state field /State/log not found") }
+            r => { panic!("expect a parse error, {:?}", r) }
+        }
+    }
+}
@@ -0,0 +1,397 @@
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::thread;
+
+use scupt_util::error_type::ET;
+use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+use scupt_util::res_of::res_io;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tracing::error;
+
+use crate::action::action_json::ActionJson;
+use crate::dtm::action_executor::ActionExecutor;
+use crate::dtm::trace_verdict::TraceStep;
+
+const STEP_COMMAND_HELP: &str = "\
+step|s                  run the current action and stop before the next one
+continue|c              run until a breakpoint
+until|u <name>          run until an action named <name>, or a breakpoint
+break|b <name>          add a breakpoint on actions named <name>
+break-node|bn <nid>     add a breakpoint on actions of node <nid>
+clear                   delete all breakpoints
+queues|q                print the pending actions of the player queues
+remaining|r             print the remaining actions of the trace
+help|h                  print this help";
+
+/// Where the step commands come from
+#[derive(Clone, Debug)]
+pub enum StepControl {
+    Stdin,
+    /// the path of a Unix socket the player listens on
+    UnixSocket(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Breakpoint {
+    ActionName(String),
+    Node(NID),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StepCommand {
+    Step,
+    Continue,
+    Until(String),
+    Break(Breakpoint),
+    Clear,
+    Queues,
+    Remaining,
+    Help,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum StepMode {
+    Step,
+    Continue,
+    Until(String),
+}
+
+enum StepOutput {
+    Stdout,
+    #[cfg(unix)]
+    Socket(Arc<StdMutex<Option<UnixStream>>>),
+    Channel(UnboundedSender<String>),
+}
+
+struct StepState {
+    mode: StepMode,
+    breakpoints: Vec<Breakpoint>,
+    commands: UnboundedReceiver<String>,
+    // the command source was closed, never stop again
+    closed: bool,
+}
+
+/// Control the player to run a trace one action at a time.
+///
+/// The player stops before each action in step mode, or before the actions hit by a breakpoint
+/// in continue mode, and waits for a command.
+pub struct StepController {
+    state: Mutex<StepState>,
+    output: StepOutput,
+}
+
+impl StepCommand {
+    pub fn parse(line: &str) -> Res<Self> {
+        let mut iter = line.split_whitespace();
+        let cmd = iter.next().unwrap_or("");
+        let arg = iter.next();
+        let command = match (cmd, arg) {
+            ("step" | "s", None) => { StepCommand::Step }
+            ("continue" | "c", None) => { StepCommand::Continue }
+            ("until" | "u", Some(name)) => { StepCommand::Until(name.to_string()) }
+            ("break" | "b", Some(name)) => {
+                StepCommand::Break(Breakpoint::ActionName(name.to_string()))
+            }
+            ("break-node" | "bn", Some(nid)) => {
+                let nid = nid.parse::<NID>()
+                    .map_err(|e| { ET::ParseError(e.to_string()) })?;
+                StepCommand::Break(Breakpoint::Node(nid))
+            }
+            ("clear", None) => { StepCommand::Clear }
+            ("queues" | "q", None) => { StepCommand::Queues }
+            ("remaining" | "r", None) => { StepCommand::Remaining }
+            ("help" | "h", None) => { StepCommand::Help }
+            _ => { return Err(ET::ParseError(format!("unknown command: {}", line))); }
+        };
+        Ok(command)
+    }
+}
+
+impl Breakpoint {
+    fn hit(&self, action: &ActionJson) -> bool {
+        match self {
+            Breakpoint::ActionName(name) => { action_name_match(action, name) }
+            Breakpoint::Node(nid) => {
+                action.source_nid().ok() == Some(*nid) || action.dest_nid().ok() == Some(*nid)
+            }
+        }
+    }
+}
+
+/// An action name such as `A::B` matches `A::B`, `A` or `B`
+fn action_name_match(action: &ActionJson, name: &str) -> bool {
+    match action.action_name() {
+        Ok(n) => { n == name || n.split("::").any(|s| { s == name }) }
+        Err(_) => { false }
+    }
+}
+
+impl StepController {
+    /// Read commands from the standard input, and print to the standard output
+    pub fn from_stdin() -> Self {
+        let (sender, receiver) = unbounded_channel();
+        let _ = thread::Builder::new()
+            .name("dtm-step-stdin".to_string())
+            .spawn(move || {
+                let stdin = std::io::stdin();
+                for line in stdin.lock().lines() {
+                    match line {
+                        Ok(l) => {
+                            if sender.send(l).is_err() {
+                                break;
+                            }
+                        }
+                        Err(_) => { break; }
+                    }
+                }
+            });
+        Self::new(receiver, StepOutput::Stdout)
+    }
+
+    /// Listen on the Unix socket `path`, read commands from the connected client, and print to it
+    #[cfg(unix)]
+    pub fn from_unix_socket(path: &str) -> Res<Self> {
+        let _ = std::fs::remove_file(path);
+        let listener = res_io(UnixListener::bind(path))?;
+        let (sender, receiver) = unbounded_channel();
+        let connection: Arc<StdMutex<Option<UnixStream>>> = Default::default();
+        let conn = connection.clone();
+        let _ = thread::Builder::new()
+            .name("dtm-step-socket".to_string())
+            .spawn(move || {
+                for r in listener.incoming() {
+                    let stream = match r {
+                        Ok(s) => { s }
+                        Err(e) => {
+                            error!("step control socket accept error {}", e);
+                            continue;
+                        }
+                    };
+                    *conn.lock().unwrap() = stream.try_clone().ok();
+                    for line in BufReader::new(stream).lines() {
+                        match line {
+                            Ok(l) => {
+                                if sender.send(l).is_err() {
+                                    return;
+                                }
+                            }
+                            Err(_) => { break; }
+                        }
+                    }
+                    *conn.lock().unwrap() = None;
+                }
+            });
+        Ok(Self::new(receiver, StepOutput::Socket(connection)))
+    }
+
+    pub fn from_step_control(control: &StepControl) -> Res<Self> {
+        match control {
+            StepControl::Stdin => { Ok(Self::from_stdin()) }
+            #[cfg(unix)]
+            StepControl::UnixSocket(path) => { Self::from_unix_socket(path.as_str()) }
+            #[cfg(not(unix))]
+            StepControl::UnixSocket(path) => {
+                Err(ET::IOError(format!(
+                    "step control on unix socket {} is not supported on this platform", path)))
+            }
+        }
+    }
+
+    /// Read commands from `commands`, and send the output to `output`
+    pub fn from_channel(
+        commands: UnboundedReceiver<String>,
+        output: UnboundedSender<String>,
+    ) -> Self {
+        Self::new(commands, StepOutput::Channel(output))
+    }
+
+    fn new(commands: UnboundedReceiver<String>, output: StepOutput) -> Self {
+        Self {
+            state: Mutex::new(StepState {
+                mode: StepMode::Step,
+                breakpoints: vec![],
+                commands,
+                closed: false,
+            }),
+            output,
+        }
+    }
+
+    fn print(&self, text: String) {
+        match &self.output {
+            StepOutput::Stdout => {
+                println!("{}", text);
+            }
+            #[cfg(unix)]
+            StepOutput::Socket(connection) => {
+                let mut guard = connection.lock().unwrap();
+                match &mut *guard {
+                    Some(stream) => {
+                        let _ = writeln!(stream, "{}", text);
+                    }
+                    None => {
+                        println!("{}", text);
+                    }
+                }
+            }
+            StepOutput::Channel(sender) => {
+                let _ = sender.send(text);
+            }
+        }
+    }
+
+    /// Invoked before running the action at `step`, return when the player can run the action
+    pub(crate) async fn before_step(
+        &self,
+        step: &TraceStep,
+        action: &ActionJson,
+        remaining: &[(ActionJson, u64)],
+        executor: &ActionExecutor,
+    ) {
+        // hold the lock, the actions of other nodes wait when stopped
+        let mut state = self.state.lock().await;
+        if state.closed {
+            return;
+        }
+        let stop = match &state.mode {
+            StepMode::Step => { true }
+            StepMode::Continue => { false }
+            StepMode::Until(name) => { action_name_match(action, name) }
+        } || state.breakpoints.iter().any(|b| { b.hit(action) });
+        if !stop {
+            return;
+        }
+
+        executor.liveness().hold(true);
+        self.print(format!("stop at {}, action: {}", step, action.serde_json_value_ref()));
+        loop {
+            let opt_line = state.commands.recv().await;
+            let line = match opt_line {
+                Some(l) => { l }
+                None => {
+                    state.closed = true;
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let command = match StepCommand::parse(line.as_str()) {
+                Ok(c) => { c }
+                Err(e) => {
+                    self.print(format!("{}\n{}", e, STEP_COMMAND_HELP));
+                    continue;
+                }
+            };
+            match command {
+                StepCommand::Step => {
+                    state.mode = StepMode::Step;
+                    break;
+                }
+                StepCommand::Continue => {
+                    state.mode = StepMode::Continue;
+                    break;
+                }
+                StepCommand::Until(name) => {
+                    state.mode = StepMode::Until(name);
+                    break;
+                }
+                StepCommand::Break(b) => {
+                    self.print(format!("add breakpoint {:?}", b));
+                    state.breakpoints.push(b);
+                }
+                StepCommand::Clear => {
+                    state.breakpoints.clear();
+                }
+                StepCommand::Queues => {
+                    let report = executor.divergence_report(action.clone()).await;
+                    let mut text = String::new();
+                    for p in report.pending.iter() {
+                        for a in p.queued.iter() {
+                            text.push_str(format!("{} queued: {}\n", p.queue, a.serde_json_value_ref()).as_str());
+                        }
+                        for a in p.waiting.iter() {
+                            text.push_str(format!("{} waiting: {}\n", p.queue, a.serde_json_value_ref()).as_str());
+                        }
+                    }
+                    self.print(text);
+                }
+                StepCommand::Remaining => {
+                    let mut text = String::new();
+                    for (a, seq_no) in remaining.iter() {
+                        text.push_str(format!("No.{}: {}\n", seq_no, a.serde_json_value_ref()).as_str());
+                    }
+                    self.print(text);
+                }
+                StepCommand::Help => {
+                    self.print(STEP_COMMAND_HELP.to_string());
+                }
+            }
+        }
+        executor.liveness().hold(false);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use tokio::runtime::Builder;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use crate::action::action_json::ActionJson;
+    use crate::dtm::action_executor::ActionExecutor;
+    use crate::dtm::liveness::Liveness;
+    use crate::dtm::step_control::{Breakpoint, StepCommand, StepController};
+    use crate::dtm::trace_verdict::TraceStep;
+
+    #[test]
+    fn test_step_command() {
+        assert_eq!(StepCommand::parse("s").unwrap(), StepCommand::Step);
+        assert_eq!(StepCommand::parse("until Vote").unwrap(), StepCommand::Until("Vote".to_string()));
+        assert_eq!(StepCommand::parse("bn 2").unwrap(), StepCommand::Break(Breakpoint::Node(2)));
+        assert!(StepCommand::parse("bn x").is_err());
+        assert!(StepCommand::parse("jump").is_err());
+    }
+
+    #[test]
+    fn test_step_controller() {
+        let action = |name: &str| {
+            ActionJson::from_value(json!({"Internal": {"source": 1, "dest": 1, "payload": {"Raft": name}}}))
+        };
+        let trace: Vec<(ActionJson, u64)> = vec![
+            (action("Tick"), 1),
+            (action("Tick"), 2),
+            (action("Elect"), 3),
+        ];
+        let (cmd_s, cmd_r) = unbounded_channel();
+        let (out_s, mut out_r) = unbounded_channel();
+        let controller = StepController::from_channel(cmd_r, out_s);
+        let executor = ActionExecutor::new(false, None, Liveness::new(1, false));
+        for c in ["r", "u Elect"] {
+            cmd_s.send(c.to_string()).unwrap();
+        }
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            for (i, (a, seq_no)) in trace.iter().enumerate() {
+                if i == 2 {
+                    cmd_s.send("c".to_string()).unwrap();
+                }
+                let step = TraceStep::new(*seq_no, None);
+                controller.before_step(&step, a, &trace[i + 1..], &executor).await;
+            }
+        });
+        let mut output = vec![];
+        while let Ok(s) = out_r.try_recv() {
+            output.push(s);
+        }
+        // stop at No.1, print remaining, run until No.3
+        assert_eq!(output.len(), 3);
+        assert!(output[0].starts_with("stop at No.1"));
+        assert!(output[1].contains("No.3"));
+        assert!(output[2].starts_with("stop at No.3"));
+    }
+}
@@ -46,6 +46,23 @@ impl SyncActionDriver for SyncActionDriverImpl {
             }
         }
     }
+
+    fn choose(&self, choice_kind: String, candidates_json_str: Vec<String>) -> Res<Option<String>> {
+        let candidates = candidates_json_str.into_iter().map(SerdeJsonString::new).collect();
+        self.inner.choose(choice_kind, candidates)
+    }
+
+    fn clock(&self, wait_until: Option<u64>) -> Res<Option<u64>> {
+        let m = MessageControl::ClockReq {
+            id: Uuid::new_v4().to_string(),
+            wait_until,
+        };
+        let response = self.inner.request(m)?;
+        match response {
+            MessageControl::ClockResp { now, .. } => { Ok(now) }
+            _ => { panic!("not possible") }
+        }
+    }
 }
 
 impl SyncActionDriverImplInner {
@@ -74,6 +91,31 @@ impl SyncActionDriverImplInner {
             action,
             begin: begin_action,
         };
+        let response = self.request(m)?;
+        match response {
+            MessageControl::ActionACK { .. } => {}
+            _ => { panic!("not possible") }
+        }
+        Ok(())
+    }
+
+    fn choose(&self, kind: String, candidates: Vec<SerdeJsonString>) -> Res<Option<String>> {
+        let uuid = Uuid::new_v4();
+        let m = MessageControl::ChooseReq {
+            id: uuid.to_string(),
+            kind,
+            candidates,
+        };
+        let response = self.request(m)?;
+        match response {
+            MessageControl::ChooseResp { value, .. } => {
+                Ok(value.map(|v| { v.to_string() }))
+            }
+            _ => { panic!("not possible") }
+        }
+    }
+
+    fn request(&self, m: MessageControl) -> Res<MessageControl> {
         let req = Message::new(m, self.node_id, self.server_id);
 
         // send request
@@ -89,11 +131,7 @@ impl SyncActionDriverImplInner {
         };
 
         trace!("receive response {:?}", response);
-        match response {
-            MessageControl::ActionACK { .. } => {}
-            _ => { panic!("not possible") }
-        }
-        Ok(())
+        Ok(response)
     }
 }
 
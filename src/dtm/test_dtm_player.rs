@@ -632,8 +632,9 @@ mod test {
         action_input: Arc<dyn ActionIncoming>,
     ) -> Res<()> {
         dtm_server.start_network(address, node_address).await?;
-        let r = dtm_server.start_dtm_test(action_input).await?;
-        let _ = r.await;
+        let r = dtm_server.start_dtm_test(0, action_input).await?;
+        let verdict = r.await.unwrap();
+        assert!(verdict.is_passed(), "{}", verdict);
         Ok(())
     }
 
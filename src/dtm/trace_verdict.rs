@@ -0,0 +1,217 @@
+use std::fmt;
+
+use scupt_util::error_type::ET;
+use scupt_util::node_id::NID;
+
+use crate::action::action_json::ActionJson;
+use crate::dtm::divergence_report::DivergenceReport;
+
+/// The position of an action in a trace.
+///
+/// It is displayed as `No.x` when the whole trace runs as one sequence, or as `No.x.y` when
+/// the trace is split into per-node sequences.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceStep {
+    /// sequence number of the action in the trace, start from 1
+    pub seq_no: u64,
+
+    /// the node id and the sequence number (start from 1) of the action in the per-node trace
+    pub node_seq_no: Option<(NID, u64)>,
+}
+
+/// The result of running a trace on the deterministic player
+#[derive(Clone, Debug)]
+pub enum TraceVerdict {
+    /// All actions of the trace were performed by the tested nodes
+    Passed {
+        trace_index: u64,
+    },
+
+    /// The player timeout waiting for the `expected` action, and in the meanwhile, the tested
+    /// nodes performed some actions which are not expected by the trace
+    Diverged {
+        trace_index: u64,
+        step: TraceStep,
+        expected: ActionJson,
+        report: DivergenceReport,
+    },
+
+    /// The player timeout waiting for the `expected` action, and the tested nodes performed
+    /// nothing else
+    Timeout {
+        trace_index: u64,
+        step: TraceStep,
+        expected: ActionJson,
+        report: DivergenceReport,
+    },
+
+    /// The trace cannot be read, or the communication with the tested nodes failed
+    ProtocolError {
+        trace_index: u64,
+        step: Option<TraceStep>,
+        expected: Option<ActionJson>,
+        message: String,
+    },
+
+    /// A tested node launched by the player exit with failure while running the trace
+    NodeCrashed {
+        trace_index: u64,
+        node_id: NID,
+        message: String,
+    },
+}
+
+impl TraceStep {
+    pub fn new(seq_no: u64, node_seq_no: Option<(NID, u64)>) -> Self {
+        Self {
+            seq_no,
+            node_seq_no,
+        }
+    }
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.node_seq_no {
+            Some((_, n)) => { write!(f, "No.{}.{}", self.seq_no, n) }
+            None => { write!(f, "No.{}", self.seq_no) }
+        }
+    }
+}
+
+impl TraceVerdict {
+    pub fn protocol_error(
+        trace_index: u64,
+        step: Option<TraceStep>,
+        expected: Option<ActionJson>,
+        error: ET,
+    ) -> Self {
+        TraceVerdict::ProtocolError {
+            trace_index,
+            step,
+            expected,
+            message: error.to_string(),
+        }
+    }
+
+    pub fn trace_index(&self) -> u64 {
+        match self {
+            TraceVerdict::Passed { trace_index } => { *trace_index }
+            TraceVerdict::Diverged { trace_index, .. } => { *trace_index }
+            TraceVerdict::Timeout { trace_index, .. } => { *trace_index }
+            TraceVerdict::ProtocolError { trace_index, .. } => { *trace_index }
+            TraceVerdict::NodeCrashed { trace_index, .. } => { *trace_index }
+        }
+    }
+
+    pub fn is_passed(&self) -> bool {
+        matches!(self, TraceVerdict::Passed { .. })
+    }
+
+    /// passed, diverged, timeout, protocol_error or node_crashed
+    pub fn status(&self) -> &'static str {
+        match self {
+            TraceVerdict::Passed { .. } => { "passed" }
+            TraceVerdict::Diverged { .. } => { "diverged" }
+            TraceVerdict::Timeout { .. } => { "timeout" }
+            TraceVerdict::ProtocolError { .. } => { "protocol_error" }
+            TraceVerdict::NodeCrashed { .. } => { "node_crashed" }
+        }
+    }
+
+    /// The step at which the trace failed
+    pub fn step(&self) -> Option<&TraceStep> {
+        match self {
+            TraceVerdict::Passed { .. } => { None }
+            TraceVerdict::Diverged { step, .. } => { Some(step) }
+            TraceVerdict::Timeout { step, .. } => { Some(step) }
+            TraceVerdict::ProtocolError { step, .. } => { step.as_ref() }
+            TraceVerdict::NodeCrashed { .. } => { None }
+        }
+    }
+
+    /// The action the player was waiting for when the trace failed
+    pub fn expected(&self) -> Option<&ActionJson> {
+        match self {
+            TraceVerdict::Passed { .. } => { None }
+            TraceVerdict::Diverged { expected, .. } => { Some(expected) }
+            TraceVerdict::Timeout { expected, .. } => { Some(expected) }
+            TraceVerdict::ProtocolError { expected, .. } => { expected.as_ref() }
+            TraceVerdict::NodeCrashed { .. } => { None }
+        }
+    }
+
+    /// The diagnostics of a diverged or timeout trace
+    pub fn report(&self) -> Option<&DivergenceReport> {
+        match self {
+            TraceVerdict::Diverged { report, .. } => { Some(report) }
+            TraceVerdict::Timeout { report, .. } => { Some(report) }
+            _ => { None }
+        }
+    }
+}
+
+impl fmt::Display for TraceVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceVerdict::Passed { trace_index } => {
+                write!(f, "trace {} passed", trace_index)
+            }
+            TraceVerdict::Diverged { trace_index, step, expected, report } => {
+                writeln!(f, "trace {} diverged at {}, expect action {}",
+                         trace_index, step, expected.serde_json_value_ref())?;
+                write!(f, "{}", report)
+            }
+            TraceVerdict::Timeout { trace_index, step, expected, report } => {
+                writeln!(f, "trace {} timeout at {}, expect action {}",
+                         trace_index, step, expected.serde_json_value_ref())?;
+                write!(f, "{}", report)
+            }
+            TraceVerdict::ProtocolError { trace_index, step, expected, message } => {
+                write!(f, "trace {} protocol error", trace_index)?;
+                if let Some(s) = step {
+                    write!(f, " at {}", s)?;
+                }
+                if let Some(e) = expected {
+                    write!(f, ", expect action {}", e.serde_json_value_ref())?;
+                }
+                write!(f, ", {}", message)
+            }
+            TraceVerdict::NodeCrashed { trace_index, node_id, message } => {
+                write!(f, "trace {} failed, node {} crashed, {}", trace_index, node_id, message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::action::action_json::ActionJson;
+    use crate::dtm::divergence_report::DivergenceReport;
+    use crate::dtm::trace_verdict::{TraceStep, TraceVerdict};
+
+    #[test]
+    fn test_trace_verdict() {
+        assert_eq!(TraceStep::new(3, None).to_string(), "No.3");
+        assert_eq!(TraceStep::new(3, Some((2, 1))).to_string(), "No.3.1");
+
+        let passed = TraceVerdict::Passed { trace_index: 1 };
+        assert!(passed.is_passed());
+        assert!(passed.step().is_none());
+
+        let expected = ActionJson::from_value(json!({"Input": {"source": 1, "dest": 2, "payload": 1}}));
+        let timeout = TraceVerdict::Timeout {
+            trace_index: 2,
+            step: TraceStep::new(5, Some((2, 3))),
+            expected: expected.clone(),
+            report: DivergenceReport::new(expected.clone(), vec![], vec![]),
+        };
+        assert!(!timeout.is_passed());
+        assert_eq!(timeout.trace_index(), 2);
+        assert_eq!(timeout.step().unwrap().seq_no, 5);
+        assert_eq!(timeout.expected(), Some(&expected));
+        assert!(timeout.to_string().contains("No.5.3"));
+    }
+}
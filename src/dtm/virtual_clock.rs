@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use scupt_util::error_type::ET;
+use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+use tokio::select;
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::action::action_json::ActionJson;
+use crate::action::action_type::ActionType;
+use crate::dtm::choice::choice_value;
+use crate::dtm::liveness::Liveness;
+
+/// The name of the Internal action advancing the virtual clock of a node
+pub const TICK_ACTION_NAME: &str = "Tick";
+
+/// The virtual clocks of the tested nodes, in milliseconds.
+///
+/// The clock of a node starts at 0, and advances only when the player reaches a Tick action of
+/// the node in the trace, such as the Internal action with payload `{"DTMTesting":{"Tick":100}}`
+/// advancing 100 milliseconds.
+#[derive(Default)]
+pub struct VirtualClock {
+    now: Mutex<HashMap<NID, u64>>,
+    notify: Notify,
+}
+
+impl VirtualClock {
+    pub fn now(&self, node_id: NID) -> u64 {
+        let now = self.now.lock().unwrap();
+        now.get(&node_id).cloned().unwrap_or_default()
+    }
+
+    /// Advance the clock of node `node_id`, and return the time after advancing
+    pub fn advance(&self, node_id: NID, millis: u64) -> u64 {
+        let mut now = self.now.lock().unwrap();
+        let t = now.entry(node_id).or_default();
+        *t += millis;
+        let t = *t;
+        self.notify.notify_waiters();
+        t
+    }
+
+    /// Set the clocks of all nodes back to 0, when a trace begins
+    pub fn reset(&self) {
+        self.now.lock().unwrap().clear();
+        self.notify.notify_waiters();
+    }
+
+    /// Wait until the clock of node `node_id` reaches `deadline`, and return the time.
+    /// Return the time before `deadline` if the trace does not advance the clock enough before
+    /// timeout.
+    pub async fn wait_until(&self, node_id: NID, deadline: u64, liveness: &Liveness) -> u64 {
+        let timeout = liveness.wait_timeout();
+        tokio::pin!(timeout);
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            // register before reading, so an advance in between is not missed
+            notified.as_mut().enable();
+            let now = self.now(node_id);
+            if now >= deadline {
+                return now;
+            }
+            select! {
+                _ = notified => {}
+                _ = &mut timeout => {
+                    warn!("node {} virtual clock {} timeout waiting for {}", node_id, now, deadline);
+                    return now;
+                }
+            }
+        }
+    }
+
+    /// The milliseconds a Tick action advances, None if the action is not a Tick action
+    pub fn tick_millis(action: &ActionJson) -> Res<Option<u64>> {
+        if action.action_type()? != ActionType::Internal {
+            return Ok(None);
+        }
+        let name = action.action_name()?;
+        if name.rsplit("::").next() != Some(TICK_ACTION_NAME) {
+            return Ok(None);
+        }
+        let opt = choice_value(&action.payload()?, TICK_ACTION_NAME);
+        match opt.as_ref().and_then(|v| { v.as_u64() }) {
+            Some(millis) => { Ok(Some(millis)) }
+            None => {
+                Err(ET::ParseError(format!("milliseconds of tick action {}", action.serde_json_value_ref())))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use crate::action::action_json::ActionJson;
+    use crate::dtm::liveness::Liveness;
+    use crate::dtm::virtual_clock::VirtualClock;
+
+    #[test]
+    fn test_virtual_clock() {
+        let tick = ActionJson::from_value(json!({
+            "Internal": {"source": 1, "dest": 1, "payload": {"DTMTesting": {"Tick": 100}}}
+        }));
+        assert_eq!(VirtualClock::tick_millis(&tick).unwrap(), Some(100));
+        let other = ActionJson::from_value(json!({
+            "Internal": {"source": 1, "dest": 1, "payload": {"DTMTesting": {"Timeout": 100}}}
+        }));
+        assert_eq!(VirtualClock::tick_millis(&other).unwrap(), None);
+
+        let clock = Arc::new(VirtualClock::default());
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let c = clock.clone();
+            let waiting = tokio::spawn(async move { c.wait_until(1, 150, &Liveness::new(60, false)).await });
+            tokio::task::yield_now().await;
+            assert_eq!(clock.advance(1, 100), 100);
+            assert_eq!(clock.advance(2, 300), 300);
+            tokio::task::yield_now().await;
+            assert!(!waiting.is_finished());
+            assert_eq!(clock.advance(1, 100), 200);
+            assert_eq!(waiting.await.unwrap(), 200);
+            assert_eq!(clock.now(1), 200);
+            let liveness = Liveness::new(1, false);
+            assert_eq!(clock.wait_until(2, 300, &liveness).await, 300);
+
+            // the time is returned when the clock does not reach the deadline before timeout
+            assert_eq!(clock.wait_until(1, 1000, &liveness).await, 200);
+
+            clock.reset();
+            assert_eq!(clock.now(1), 0);
+            assert_eq!(clock.now(2), 0);
+        });
+    }
+}
@@ -1,6 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::read_to_string;
 use std::net::SocketAddr;
+use std::process::exit;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use clap::Parser;
 use scupt_net::notifier::Notifier;
@@ -9,14 +13,24 @@ use scupt_util::node_id::NID;
 use scupt_util::res::Res;
 use scupt_util::res_of::{res_io, res_parse};
 use toml;
-use tracing::debug;
+use tracing::{debug, error, info};
 
-use player_conf::PlayerConf;
+use player_conf::{AddressSet, PlayerConf, TraceSelection, TraceTestOption};
+use report::{ReportSpec, ReportWriter};
 use sedeve_kit::dtm::action_incoming_factory::ActionIncomingFactory;
+use sedeve_kit::dtm::action_recorder::ActionRecorder;
 use sedeve_kit::dtm::dtm_player::DTMPlayer;
-use sedeve_kit::trace::trace_db::TraceDB;
+use sedeve_kit::dtm::node_supervisor::{LaunchContext, NodeSupervisor};
+use sedeve_kit::dtm::step_control::{StepControl, StepController};
+use sedeve_kit::dtm::trace_verdict::TraceVerdict;
+use sedeve_kit::trace::spec_graph::SpecGraph;
+use sedeve_kit::trace::trace_db::{TraceDB, TraceResult};
 
 mod player_conf;
+mod report;
+
+/// the trace of a monitor or record run, which is not read by the player
+const EMPTY_TRACE: &str = r#"{"actions":[]}"#;
 
 /// action definition to Rust code template
 #[derive(Parser, Debug)]
@@ -25,54 +39,333 @@ struct Args {
     /// player config path
     #[arg(short, long)]
     pub conf_path: String,
+
+    /// run the traces step by step, read the step commands from the standard input
+    #[arg(long, default_value_t = false)]
+    pub step: bool,
+
+    /// run the traces step by step, read the step commands from a Unix socket at this path
+    #[arg(long)]
+    pub step_socket: Option<String>,
+
+    /// only run the traces failed in the previous run
+    #[arg(long, default_value_t = false)]
+    pub only_failed: bool,
+
+    /// skip the traces passed in the previous run
+    #[arg(long, default_value_t = false)]
+    pub resume: bool,
+
+    /// only run the traces with these ids
+    #[arg(long)]
+    pub trace_id: Vec<String>,
+
+    /// write a report of the results, `junit=<path>` for JUnit XML, `jsonl=<path>` for JSON lines
+    #[arg(long, value_parser = ReportSpec::parse)]
+    pub report: Vec<ReportSpec>,
 }
 
-fn player_gut(db_path: String, player_id: NID, player_address: SocketAddr, peers: HashMap<NID, SocketAddr>) -> Res<()> {
-    let db = TraceDB::new(db_path)?;
-
-    let vec = db.read_trace()?;
-    for (i, s) in vec.iter().enumerate() {
-        let notifier = Notifier::new();
-        let n = notifier.clone();
-        let incoming = ActionIncomingFactory::action_incoming_from_string(s.clone())?;
-        let f_done = move || {
-            n.task_notify_all();
-        };
-        debug!("DTM player run trace {} {}", i + 1, s);
-        DTMPlayer::run_trace(
-            player_id,
-            player_address.clone(),
-            peers.clone(),
-            incoming,
-            notifier.clone(),
-            Default::default(),
-            f_done,
-        )?;
+/// Select the traces to run by the command line arguments and the `[selection]` of the config,
+/// return (trace index, trace id, trace json)
+fn select_trace(
+    traces: Vec<(String, String)>,
+    results: Vec<TraceResult>,
+    args: &Args,
+    selection: &TraceSelection,
+) -> Res<Vec<(u64, String, String)>> {
+    let mut passed = HashSet::new();
+    let mut failed = HashSet::new();
+    for r in results {
+        if r.status == "passed" {
+            passed.insert(r.trace_id);
+        } else {
+            failed.insert(r.trace_id);
+        }
     }
-    Ok(())
+    let ids: HashSet<&String> = args.trace_id.iter().collect();
+    let vec = traces.into_iter()
+        .enumerate()
+        .filter(|(_, (id, _))| {
+            (ids.is_empty() || ids.contains(id))
+                && (!args.only_failed || failed.contains(id))
+                && (!args.resume || !passed.contains(id))
+        })
+        .map(|(i, (id, json))| { (i as u64, id, json) })
+        .collect();
+    selection.select(vec)
 }
 
-fn player_run(conf: String) -> Res<()> {
-    let s = res_io(read_to_string(conf))?;
-    let c: PlayerConf = toml::from_str(s.as_str()).unwrap();
-    let player_addr: SocketAddr = res_parse(c.player_addr.addr.parse())?;
-    let mut peers = HashMap::new();
+fn trace_result(trace_id: String, verdict: &TraceVerdict, duration_millis: u64) -> TraceResult {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| { d.as_secs() });
+    let error = if verdict.is_passed() {
+        None
+    } else {
+        verdict.to_string().lines().next().map(|s| { s.to_string() })
+    };
+    TraceResult {
+        trace_id,
+        status: verdict.status().to_string(),
+        failing_step: verdict.step().map(|s| { s.seq_no }),
+        duration_millis,
+        timestamp,
+        error,
+    }
+}
+
+/// The addresses of a player and the tested nodes, on which a trace runs isolated
+#[derive(Clone)]
+struct PlayerAddress {
+    player_id: NID,
+    player_address: SocketAddr,
+    peers: HashMap<NID, SocketAddr>,
+}
+
+impl PlayerAddress {
+    fn from_address_set(address_set: &AddressSet) -> Res<Self> {
+        let player_address: SocketAddr = res_parse(address_set.player_addr.addr.parse())?;
+        let mut peers = HashMap::new();
+        for addr in address_set.peer_addr.iter() {
+            let a: SocketAddr = res_parse(addr.addr.parse())?;
+            peers.insert(addr.nid.clone(), a);
+        }
+        Ok(Self {
+            player_id: address_set.player_addr.nid,
+            player_address,
+            peers,
+        })
+    }
+}
+
+fn run_one_trace(
+    db: &Arc<TraceDB>,
+    address: &PlayerAddress,
+    index: u64,
+    trace_id: &String,
+    s: &String,
+    step_controller: &Option<Arc<StepController>>,
+    conf: &PlayerConf,
+    spec_graph: &Option<Arc<SpecGraph>>,
+    test_option: &TraceTestOption,
+) -> Res<(TraceResult, TraceVerdict)> {
+    let begin = Instant::now();
+    let opt_supervisor = match &conf.launch {
+        Some(l) => {
+            let context = LaunchContext {
+                trace_id: trace_id.clone(),
+                trace_index: index,
+                player_nid: address.player_id,
+                player_addr: address.player_address.clone(),
+                peers: address.peers.clone(),
+                log_dir: l.log_dir.clone(),
+            };
+            match NodeSupervisor::launch(&l.node, &context) {
+                Ok(s) => { Some(Arc::new(s)) }
+                Err(e) => {
+                    let verdict = TraceVerdict::protocol_error(index, None, None, e);
+                    error!("{}", verdict);
+                    let result = trace_result(trace_id.clone(), &verdict, begin.elapsed().as_millis() as u64);
+                    db.write_trace_result(&result)?;
+                    return Ok((result, verdict));
+                }
+            }
+        }
+        None => { None }
+    };
+    let notifier = Notifier::new();
+    let n = notifier.clone();
+    let incoming = ActionIncomingFactory::action_incoming_from_string(s.clone())?;
+    let f_done = move || {
+        n.task_notify_all();
+    };
+    debug!("DTM player run trace {} {} {}", index + 1, trace_id, s);
+    let mut option = test_option.option(trace_id).to_test_option();
+    if let Some(c) = step_controller {
+        option = option.set_step_controller(c.clone());
+    }
+    if let Some(s) = &opt_supervisor {
+        option = option.set_node_supervisor(s.clone());
+    }
+    if let Some(p) = &conf.proxy {
+        option = option.set_proxy(p.clone());
+    }
+    if let Some(g) = spec_graph {
+        option = option.set_spec_graph(g.clone());
+    }
+    let opt_recorder = conf.record.as_ref().map(|_| {
+        Arc::new(ActionRecorder::new(db.clone(), trace_id.clone()))
+    });
+    if let Some(r) = &opt_recorder {
+        option = option.set_recorder(r.clone());
+    }
+    let verdict = DTMPlayer::run_trace(
+        address.player_id,
+        address.player_address.clone(),
+        address.peers.clone(),
+        index,
+        incoming,
+        notifier.clone(),
+        option,
+        f_done,
+    )?;
+    if let Some(s) = &opt_supervisor {
+        s.shutdown();
+    }
+    if verdict.is_passed() {
+        info!("{}", verdict);
+    } else {
+        error!("{}", verdict);
+    }
+    if let (Some(r), Some(record)) = (&opt_recorder, &conf.record) {
+        info!("run {} recorded {} actions", r.run_id(), r.num_recorded());
+        if record.to_trace {
+            db.write_recorded_trace(r.run_id())?;
+        }
+    }
+    let duration_millis = begin.elapsed().as_millis() as u64;
+    let result = trace_result(trace_id.clone(), &verdict, duration_millis);
+    db.write_trace_result(&result)?;
+    Ok((result, verdict))
+}
 
+/// Run the selected traces, at most `parallelism` traces run concurrently, each on its own
+/// address set. The results are in trace order.
+fn player_gut(
+    conf: &PlayerConf,
+    addresses: Vec<PlayerAddress>,
+    args: &Args,
+    step_controller: Option<Arc<StepController>>,
+) -> Res<Vec<(TraceResult, TraceVerdict)>> {
+    conf.check_mode()?;
+    let db = Arc::new(TraceDB::new(conf.trace_db_path.clone())?);
+    let test_option = conf.trace_test_option()?;
+
+    let (vec, opt_graph) = match (&conf.monitor, &conf.record) {
+        (_, Some(r)) => {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| { d.as_secs() });
+            let runs = (0..r.runs)
+                .map(|i| { (i, format!("record_{}_{}", timestamp, i + 1), EMPTY_TRACE.to_string()) })
+                .collect();
+            (runs, None)
+        }
+        (Some(m), None) => {
+            let graph = SpecGraph::from_db(m.spec_db_path.clone())?;
+            info!("monitor mode, the state graph has {} states", graph.num_states());
+            let runs = (0..m.runs)
+                .map(|i| { (i, format!("monitor_{}", i + 1), EMPTY_TRACE.to_string()) })
+                .collect();
+            (runs, Some(Arc::new(graph)))
+        }
+        (None, None) => {
+            let traces = db.read_trace_with_id()?;
+            let results = db.read_trace_result()?;
+            (select_trace(traces, results, args, &conf.selection)?, None)
+        }
+    };
+    // the proxy and the step controller are shared by all traces, so the traces run one by one
+    let num_worker = if conf.proxy.is_some() || step_controller.is_some() {
+        1
+    } else {
+        conf.parallelism.max(1).min(addresses.len())
+    };
+    let queue = Mutex::new(vec.into_iter().enumerate().collect::<VecDeque<_>>());
+    let verdicts = Mutex::new(vec![]);
+    let report_writer = Mutex::new(ReportWriter::create(&args.report)?);
+    let opt_error = Mutex::new(None);
+    thread::scope(|scope| {
+        for address in addresses.iter().take(num_worker) {
+            let (db, queue, verdicts, report_writer, opt_error) =
+                (&db, &queue, &verdicts, &report_writer, &opt_error);
+            let (step_controller, opt_graph, test_option) = (&step_controller, &opt_graph, &test_option);
+            scope.spawn(move || {
+                loop {
+                    let opt_trace = queue.lock().unwrap().pop_front();
+                    let (pos, (i, trace_id, s)) = match opt_trace {
+                        Some(t) => { t }
+                        None => { break; }
+                    };
+                    let r = run_one_trace(
+                        db, address, i, &trace_id, &s, step_controller,
+                        conf, opt_graph, test_option);
+                    let r = r.and_then(|(result, verdict)| {
+                        report_writer.lock().unwrap().append(&result, &verdict)?;
+                        verdicts.lock().unwrap().push((pos, result, verdict));
+                        Ok(())
+                    });
+                    match r {
+                        Ok(()) => {}
+                        Err(e) => {
+                            *opt_error.lock().unwrap() = Some(e);
+                            queue.lock().unwrap().clear();
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    let mut verdicts = verdicts.into_inner().unwrap();
+    verdicts.sort_by_key(|(pos, _, _)| { *pos });
+    let results = verdicts.into_iter().map(|(_, result, verdict)| { (result, verdict) }).collect();
+    // the reports keep the traces finished before an error
+    let r = report_writer.into_inner().unwrap().finish(&results);
+    if let Some(e) = opt_error.into_inner().unwrap() {
+        return Err(e);
+    }
+    r?;
+    Ok(results)
+}
+
+/// print the summary of all traces, return the number of failed traces
+fn player_summary(verdicts: &Vec<(TraceResult, TraceVerdict)>) -> usize {
+    let failed: Vec<&TraceVerdict> = verdicts.iter()
+        .map(|(_, v)| { v })
+        .filter(|v| { !v.is_passed() })
+        .collect();
+    println!("run {} traces, {} passed, {} failed", verdicts.len(), verdicts.len() - failed.len(), failed.len());
+    for v in failed.iter() {
+        println!("{}", v);
+    }
+    failed.len()
+}
+
+fn player_run(args: &Args) -> Res<Vec<(TraceResult, TraceVerdict)>> {
+    let s = res_io(read_to_string(args.conf_path.clone()))?;
+    let c: PlayerConf = toml::from_str(s.as_str()).unwrap();
 
     logger_setup(c.log_level.as_str());
 
-    for addr in c.peer_addr.iter() {
-        let a: SocketAddr = res_parse(addr.addr.parse())?;
-        peers.insert(addr.nid.clone(), a);
+    let mut addresses = vec![];
+    for address_set in c.address_sets().iter() {
+        addresses.push(PlayerAddress::from_address_set(address_set)?);
     }
 
+    let opt_step_control = match &args.step_socket {
+        Some(path) => { Some(StepControl::UnixSocket(path.clone())) }
+        None => {
+            if args.step {
+                Some(StepControl::Stdin)
+            } else {
+                None
+            }
+        }
+    };
+    let step_controller = match opt_step_control {
+        Some(control) => { Some(Arc::new(StepController::from_step_control(&control)?)) }
+        None => { None }
+    };
 
-    player_gut(c.trace_db_path, c.player_addr.nid, player_addr, peers)?;
-    Ok(())
+    player_gut(&c, addresses, args, step_controller)
 }
 
 fn main() {
     let args = Args::parse();
-    let conf_path: String = args.conf_path.clone();
-    player_run(conf_path).unwrap();
+    let verdicts = player_run(&args).unwrap();
+    let num_failed = player_summary(&verdicts);
+    if num_failed > 0 {
+        exit(1);
+    }
 }
\ No newline at end of file
@@ -1,5 +1,21 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use regex::Regex;
+use scupt_util::error_type::ET;
 use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+use sedeve_kit::action::action_json::ActionJson;
+use sedeve_kit::action::trace::TraceJsonValue;
+use sedeve_kit::dtm::dtm_player::TestOption;
+use sedeve_kit::dtm::state_projection::FieldProjection;
+use sedeve_kit::dtm::dtm_proxy::ProxyOption;
+use sedeve_kit::dtm::node_supervisor::NodeLaunch;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(
 Clone,
@@ -12,6 +28,143 @@ pub struct NodeAddr {
     pub addr: String,
 }
 
+#[derive(
+Clone,
+Serialize,
+Debug,
+Deserialize,
+)]
+pub struct AddressSet {
+    pub player_addr: NodeAddr,
+    pub peer_addr: Vec<NodeAddr>,
+}
+
+/// The tested nodes the player launches before each trace and kills after it
+#[derive(
+Clone,
+Serialize,
+Debug,
+Deserialize,
+)]
+pub struct LaunchConf {
+    /// the stdout/stderr of the nodes are written to `<log_dir>/<trace_id>/`
+    #[serde(default = "default_log_dir")]
+    pub log_dir: String,
+    pub node: Vec<NodeLaunch>,
+}
+
+/// Monitor mode, in which the player checks the runs of the tested nodes against the state graph
+/// of the specification, rather than running the traces
+#[derive(
+Clone,
+Serialize,
+Debug,
+Deserialize,
+PartialEq,
+)]
+pub struct MonitorConf {
+    /// the intermediate database, whose `action` table is the state graph
+    pub spec_db_path: String,
+    /// the number of runs, the tested nodes are launched again for each run if `launch` is set
+    #[serde(default = "default_runs")]
+    pub runs: u64,
+}
+
+/// Record mode, in which the player acknowledges and records every action of the tested nodes to
+/// the `recorded_action` table of the trace database, rather than running the traces
+#[derive(
+Clone,
+Serialize,
+Debug,
+Deserialize,
+PartialEq,
+)]
+pub struct RecordConf {
+    /// the number of runs, the tested nodes are launched again for each run if `launch` is set
+    #[serde(default = "default_runs")]
+    pub runs: u64,
+    /// also write each recorded run to the `trace` table, which can be replayed as a trace
+    #[serde(default)]
+    pub to_trace: bool,
+}
+
+/// The options of a test, an option not set takes the value of `[test_option]`, or the default
+/// value of `TestOption`
+#[derive(
+Clone,
+Serialize,
+Debug,
+Deserialize,
+Default,
+PartialEq,
+)]
+pub struct TestOptionConf {
+    #[serde(default)]
+    pub wait_both_begin_and_end_action: Option<bool>,
+    #[serde(default)]
+    pub output_action_sequential: Option<bool>,
+    #[serde(default)]
+    pub seconds_wait_message_timeout: Option<u64>,
+    #[serde(default)]
+    pub debug_mode: Option<bool>,
+    /// the JSON pointers of the state compared by Check actions, such as `/State/term`, the
+    /// whole state is compared by default
+    #[serde(default)]
+    pub check_state_fields: Option<Vec<String>>,
+}
+
+/// The options override `[test_option]` for the traces with the id `id`, or the ids matching
+/// the regular expression `id_pattern`
+#[derive(
+Clone,
+Serialize,
+Debug,
+Deserialize,
+)]
+pub struct TraceOptionConf {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub id_pattern: Option<String>,
+    #[serde(flatten)]
+    pub option: TestOptionConf,
+}
+
+/// The traces to run, a trace is selected when it satisfies all the conditions set
+#[derive(
+Clone,
+Serialize,
+Debug,
+Deserialize,
+Default,
+)]
+pub struct TraceSelection {
+    /// the ids of the traces
+    #[serde(default)]
+    pub ids: Vec<String>,
+
+    /// the first index of the traces, start from 0
+    #[serde(default)]
+    pub index_begin: Option<u64>,
+
+    /// the index after the last index of the traces
+    #[serde(default)]
+    pub index_end: Option<u64>,
+
+    /// the traces having an action with one of these names, such as `DTMTesting::Restart`, see
+    /// `ActionJson::action_name`
+    #[serde(default)]
+    pub action_names: Vec<String>,
+
+    /// randomly sample this number of traces from the traces selected by the conditions above
+    #[serde(default)]
+    pub sample: Option<usize>,
+
+    /// the seed of the random sample
+    #[serde(default)]
+    pub seed: u64,
+}
+
 #[derive(
 Clone,
 Serialize,
@@ -23,12 +176,212 @@ pub struct PlayerConf {
     pub trace_db_path: String,
     pub player_addr: NodeAddr,
     pub peer_addr: Vec<NodeAddr>,
+
+    /// extra address sets, a trace runs isolated on one of the `player_addr`/`peer_addr` set or
+    /// these sets. The node ids of the peers must be the same in every set.
+    #[serde(default)]
+    pub address_pool: Vec<AddressSet>,
+
+    /// the number of traces run concurrently, which is limited by the number of address sets.
+    /// The traces run one by one when a proxy or step mode is used.
+    #[serde(default = "default_parallelism")]
+    pub parallelism: usize,
+
+    /// when launch is set, the player launches the tested nodes itself, rather than connects to
+    /// the nodes started by others
+    #[serde(default)]
+    pub launch: Option<LaunchConf>,
+
+    /// the test options of all traces
+    #[serde(default)]
+    pub test_option: TestOptionConf,
+
+    /// the test options of some traces, a latter one takes precedence over a former one
+    #[serde(default)]
+    pub trace_option: Vec<TraceOptionConf>,
+
+    /// the traces to run, all traces are run by default
+    #[serde(default)]
+    pub selection: TraceSelection,
+
+    /// when proxy is set, the messages between the tested nodes go through the player, the
+    /// proxy listens on fixed addresses, so the traces cannot run concurrently
+    #[serde(default)]
+    pub proxy: Option<ProxyOption>,
+
+    /// when monitor is set, the player runs as a conformance monitor and the traces are not run
+    #[serde(default)]
+    pub monitor: Option<MonitorConf>,
+
+    /// when record is set, the player records the actions of the tested nodes, the traces are
+    /// not run
+    #[serde(default)]
+    pub record: Option<RecordConf>,
+}
+
+/// The test options of the traces, resolved from `[test_option]` and `[[trace_option]]`
+pub struct TraceTestOption {
+    global: TestOptionConf,
+    overrides: Vec<(Option<String>, Option<Regex>, TestOptionConf)>,
+}
+
+fn default_parallelism() -> usize {
+    1
+}
+
+fn default_log_dir() -> String {
+    "node_log".to_string()
+}
+
+fn default_runs() -> u64 {
+    1
+}
+
+impl PlayerConf {
+    /// Record mode and monitor mode both take the place of running the traces, and cannot be
+    /// configured together
+    pub fn check_mode(&self) -> Res<()> {
+        if self.record.is_some() && self.monitor.is_some() {
+            return Err(ET::ParseError("record and monitor cannot be configured together".to_string()));
+        }
+        Ok(())
+    }
+
+    /// All address sets, the first one is `player_addr`/`peer_addr`
+    pub fn address_sets(&self) -> Vec<AddressSet> {
+        let mut vec = vec![AddressSet {
+            player_addr: self.player_addr.clone(),
+            peer_addr: self.peer_addr.clone(),
+        }];
+        vec.extend(self.address_pool.iter().cloned());
+        vec
+    }
+
+    pub fn trace_test_option(&self) -> Res<TraceTestOption> {
+        let mut overrides = vec![];
+        for o in self.trace_option.iter() {
+            let opt_regex = match &o.id_pattern {
+                Some(p) => {
+                    let r = Regex::new(format!("^(?:{})$", p).as_str());
+                    match r {
+                        Ok(r) => { Some(r) }
+                        Err(e) => { return Err(ET::ParseError(e.to_string())); }
+                    }
+                }
+                None => { None }
+            };
+            overrides.push((o.id.clone(), opt_regex, o.option.clone()));
+        }
+        Ok(TraceTestOption {
+            global: self.test_option.clone(),
+            overrides,
+        })
+    }
+}
+
+impl TestOptionConf {
+    /// The options set in `other` take precedence over the options of `self`
+    pub fn merge(&self, other: &TestOptionConf) -> TestOptionConf {
+        TestOptionConf {
+            wait_both_begin_and_end_action: other.wait_both_begin_and_end_action
+                .or(self.wait_both_begin_and_end_action),
+            output_action_sequential: other.output_action_sequential
+                .or(self.output_action_sequential),
+            seconds_wait_message_timeout: other.seconds_wait_message_timeout
+                .or(self.seconds_wait_message_timeout),
+            debug_mode: other.debug_mode.or(self.debug_mode),
+            check_state_fields: other.check_state_fields.clone()
+                .or(self.check_state_fields.clone()),
+        }
+    }
+
+    pub fn to_test_option(&self) -> TestOption {
+        let mut option = TestOption::default();
+        if let Some(b) = self.wait_both_begin_and_end_action {
+            option = option.set_wait_both_begin_and_end_action(b);
+        }
+        if let Some(b) = self.output_action_sequential {
+            option = option.set_sequential_output_action(b);
+        }
+        if let Some(s) = self.seconds_wait_message_timeout {
+            option = option.set_seconds_wait_message_timeout(s);
+        }
+        if let Some(b) = self.debug_mode {
+            option = option.set_debug_mode(b);
+        }
+        if let Some(fields) = &self.check_state_fields {
+            option = option.set_state_projection(Arc::new(FieldProjection::new(fields.clone())));
+        }
+        option
+    }
+}
+
+impl TraceTestOption {
+    /// The test option of the trace with id `trace_id`
+    pub fn option(&self, trace_id: &str) -> TestOptionConf {
+        let mut option = self.global.clone();
+        for (opt_id, opt_regex, o) in self.overrides.iter() {
+            let id_match = opt_id.as_ref().map_or(false, |id| { id == trace_id });
+            let pattern_match = opt_regex.as_ref().map_or(false, |r| { r.is_match(trace_id) });
+            if id_match || pattern_match {
+                option = option.merge(o);
+            }
+        }
+        option
+    }
+}
+
+impl TraceSelection {
+    /// Select from (trace index, trace id, trace json), the traces are kept in order
+    pub fn select(&self, traces: Vec<(u64, String, String)>) -> Res<Vec<(u64, String, String)>> {
+        let ids: HashSet<&String> = self.ids.iter().collect();
+        let mut selected = vec![];
+        for (index, id, json) in traces {
+            if !ids.is_empty() && !ids.contains(&id) {
+                continue;
+            }
+            if self.index_begin.map_or(false, |i| { index < i })
+                || self.index_end.map_or(false, |i| { index >= i }) {
+                continue;
+            }
+            if !self.action_names.is_empty() && !self.has_action_name(&json)? {
+                continue;
+            }
+            selected.push((index, id, json));
+        }
+        if let Some(n) = self.sample {
+            let mut rng = StdRng::seed_from_u64(self.seed);
+            let mut sampled: Vec<_> = selected.choose_multiple(&mut rng, n).cloned().collect();
+            sampled.sort_by_key(|(index, _, _)| { *index });
+            selected = sampled;
+        }
+        Ok(selected)
+    }
+
+    fn has_action_name(&self, trace_json: &str) -> Res<bool> {
+        let value: Value = match serde_json::from_str(trace_json) {
+            Ok(v) => { v }
+            Err(e) => { return Err(ET::SerdeError(e.to_string())); }
+        };
+        let trace = TraceJsonValue::from_json_value(value)?;
+        for a in trace.actions {
+            if let Ok(name) = ActionJson::from_value(a).action_name() {
+                if self.action_names.contains(&name) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
 }
 
 
 #[cfg(test)]
 mod test {
-    use crate::player_conf::{NodeAddr, PlayerConf};
+    use sedeve_kit::dtm::dtm_proxy::MessageFraming;
+    use sedeve_kit::dtm::node_supervisor::ReadinessProbe;
+
+    use crate::player_conf::{AddressSet, NodeAddr, PlayerConf, RecordConf, TestOptionConf};
 
     #[test]
     fn test_conf() {
@@ -43,10 +396,182 @@ mod test {
                 nid: 1,
                 addr: "127.0.0.1:8000".to_string(),
             },
-            peer_addr: node_peer,
+            peer_addr: node_peer.clone(),
+            address_pool: vec![AddressSet {
+                player_addr: NodeAddr {
+                    nid: 1,
+                    addr: "127.0.0.1:9000".to_string(),
+                },
+                peer_addr: node_peer,
+            }],
+            parallelism: 2,
+            launch: None,
+            test_option: Default::default(),
+            trace_option: vec![],
+            selection: Default::default(),
+            proxy: None,
+            monitor: None,
+            record: None,
         };
 
         let s = toml::to_string(&conf).unwrap();
-        println!("{}", s)
+        println!("{}", s);
+        let c: PlayerConf = toml::from_str(s.as_str()).unwrap();
+        assert_eq!(c.address_sets().len(), 2);
+
+        let s = r#"
+log_level = "debug"
+trace_db_path = "/tmp/trace.db"
+
+[player_addr]
+nid = 1
+addr = "127.0.0.1:8000"
+
+[[peer_addr]]
+nid = 2
+addr = "127.0.0.1:8002"
+"#;
+        let c: PlayerConf = toml::from_str(s).unwrap();
+        assert_eq!(c.parallelism, 1);
+        assert_eq!(c.address_sets().len(), 1);
+        assert!(c.launch.is_none());
+        assert!(c.proxy.is_none());
+        assert!(c.monitor.is_none());
+        assert!(c.record.is_none());
+
+        let s = r#"
+log_level = "debug"
+trace_db_path = "/tmp/trace.db"
+
+[player_addr]
+nid = 1
+addr = "127.0.0.1:8000"
+
+[[peer_addr]]
+nid = 2
+addr = "127.0.0.1:8002"
+
+[launch]
+log_dir = "/tmp/echo_log"
+
+[[launch.node]]
+nid = 2
+argv = ["python3", "echo.py", "--address", "{addr}", "--player", "{player_addr}"]
+env = { TRACE_ID = "{trace_id}" }
+ready = { kind = "tcp", addr = "{addr}" }
+
+[record]
+to_trace = true
+
+[monitor]
+spec_db_path = "/tmp/echo/action.db"
+
+[proxy]
+framing = "line"
+
+[[proxy.link]]
+source = 2
+dest = 3
+listen_addr = "127.0.0.1:9023"
+dest_addr = "127.0.0.1:8003"
+"#;
+        let c: PlayerConf = toml::from_str(s).unwrap();
+        assert_eq!(c.monitor.as_ref().unwrap().runs, 1);
+        assert_eq!(c.record, Some(RecordConf { runs: 1, to_trace: true }));
+        let proxy = c.proxy.unwrap();
+        assert_eq!(proxy.framing, MessageFraming::Line);
+        assert_eq!(proxy.link[0].dest, 3);
+        let launch = c.launch.unwrap();
+        assert_eq!(launch.node.len(), 1);
+        assert_eq!(launch.node[0].ready, Some(ReadinessProbe::Tcp {
+            addr: "{addr}".to_string(),
+            seconds_timeout: 30,
+        }));
+    }
+
+    #[test]
+    fn test_conf_record_with_monitor() {
+        let s = r#"
+log_level = "debug"
+trace_db_path = "/tmp/trace.db"
+
+[player_addr]
+nid = 1
+addr = "127.0.0.1:8000"
+
+[[peer_addr]]
+nid = 2
+addr = "127.0.0.1:8002"
+
+[record]
+
+[monitor]
+spec_db_path = "/tmp/echo/action.db"
+"#;
+        let mut c: PlayerConf = toml::from_str(s).unwrap();
+        assert!(c.check_mode().is_err());
+        c.monitor = None;
+        assert!(c.check_mode().is_ok());
+        c.record = None;
+        assert!(c.check_mode().is_ok());
+    }
+
+    #[test]
+    fn test_conf_test_option_and_selection() {
+        let s = r#"
+log_level = "debug"
+trace_db_path = "/tmp/trace.db"
+
+[player_addr]
+nid = 1
+addr = "127.0.0.1:8000"
+
+[[peer_addr]]
+nid = 2
+addr = "127.0.0.1:8002"
+
+[test_option]
+seconds_wait_message_timeout = 10
+output_action_sequential = true
+
+[[trace_option]]
+id_pattern = "slow_.*"
+seconds_wait_message_timeout = 60
+
+[[trace_option]]
+id = "slow_2"
+output_action_sequential = false
+
+[selection]
+index_begin = 1
+action_names = ["DTMTesting::Restart"]
+sample = 2
+seed = 7
+"#;
+        let c: PlayerConf = toml::from_str(s).unwrap();
+        let option = c.trace_test_option().unwrap();
+        assert_eq!(option.option("fast_1"), TestOptionConf {
+            wait_both_begin_and_end_action: None,
+            output_action_sequential: Some(true),
+            seconds_wait_message_timeout: Some(10),
+            debug_mode: None,
+            check_state_fields: None,
+        });
+        assert_eq!(option.option("slow_1").seconds_wait_message_timeout, Some(60));
+        assert_eq!(option.option("slow_2").output_action_sequential, Some(false));
+        assert_eq!(option.option("a_slow_2").seconds_wait_message_timeout, Some(10));
+        assert_eq!(option.option("slow_1").to_test_option().seconds_wait_message_timeout, 60);
+
+        let restart = r#"{"actions": [{"Internal": {"source": 1, "dest": 1, "payload": {"DTMTesting": {"Restart": 1}}}}]}"#;
+        let other = r#"{"actions": [{"Internal": {"source": 1, "dest": 1, "payload": {"DTMTesting": {"Crash": 1}}}}]}"#;
+        let traces: Vec<(u64, String, String)> = (0..6u64).map(|i| {
+            let json = if i % 2 == 0 { other } else { restart };
+            (i, format!("t{}", i), json.to_string())
+        }).collect();
+        let selected = c.selection.select(traces.clone()).unwrap();
+        assert_eq!(selected.len(), 2);
+        assert!(selected.iter().all(|(i, _, _)| { i % 2 == 1 }));
+        assert!(selected[0].0 < selected[1].0);
+        assert_eq!(selected, c.selection.select(traces).unwrap());
     }
 }
\ No newline at end of file
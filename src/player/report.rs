@@ -0,0 +1,254 @@
+use std::fs::File;
+use std::io::Write;
+
+use scupt_util::res::Res;
+use scupt_util::res_of::res_io;
+use serde_json::json;
+
+use sedeve_kit::dtm::trace_verdict::TraceVerdict;
+use sedeve_kit::trace::trace_db::TraceResult;
+
+const TEST_SUITE_NAME: &str = "sedeve_player";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReportFormat {
+    JUnit,
+    JsonLines,
+}
+
+/// A report to write, specified by `junit=<path>` or `jsonl=<path>`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportSpec {
+    pub format: ReportFormat,
+    pub path: String,
+}
+
+impl ReportSpec {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (format, path) = match s.split_once('=') {
+            Some(kv) => { kv }
+            None => { return Err(format!("expect junit=<path> or jsonl=<path>, found {}", s)); }
+        };
+        let format = match format {
+            "junit" => { ReportFormat::JUnit }
+            "jsonl" => { ReportFormat::JsonLines }
+            _ => { return Err(format!("unknown report format {}", format)); }
+        };
+        Ok(Self {
+            format,
+            path: path.to_string(),
+        })
+    }
+
+    pub fn write(&self, results: &Vec<(TraceResult, TraceVerdict)>) -> Res<()> {
+        let text = match self.format {
+            ReportFormat::JUnit => { junit_report(results) }
+            ReportFormat::JsonLines => { jsonl_report(results) }
+        };
+        let mut file = res_io(File::create(self.path.clone()))?;
+        res_io(file.write_all(text.as_bytes()))?;
+        Ok(())
+    }
+}
+
+/// The reports of a player run.
+/// A JSON lines report gets one line as each trace finishes, in the order the traces finish.
+/// A JUnit report is written once, when the run finishes or stops on an error.
+pub struct ReportWriter {
+    jsonl: Vec<File>,
+    junit: Vec<ReportSpec>,
+}
+
+impl ReportWriter {
+    pub fn create(reports: &[ReportSpec]) -> Res<Self> {
+        let mut jsonl = vec![];
+        let mut junit = vec![];
+        for report in reports.iter() {
+            match report.format {
+                ReportFormat::JsonLines => { jsonl.push(res_io(File::create(report.path.clone()))?); }
+                ReportFormat::JUnit => { junit.push(report.clone()); }
+            }
+        }
+        Ok(Self { jsonl, junit })
+    }
+
+    /// Append the result of a finished trace
+    pub fn append(&mut self, result: &TraceResult, verdict: &TraceVerdict) -> Res<()> {
+        if self.jsonl.is_empty() {
+            return Ok(());
+        }
+        let line = jsonl_line(result, verdict);
+        for file in self.jsonl.iter_mut() {
+            res_io(file.write_all(line.as_bytes()))?;
+        }
+        Ok(())
+    }
+
+    /// Write the reports which need the results of all finished traces
+    pub fn finish(&self, results: &Vec<(TraceResult, TraceVerdict)>) -> Res<()> {
+        for report in self.junit.iter() {
+            report.write(results)?;
+        }
+        Ok(())
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => { escaped.push_str("&amp;") }
+            '<' => { escaped.push_str("&lt;") }
+            '>' => { escaped.push_str("&gt;") }
+            '"' => { escaped.push_str("&quot;") }
+            '\'' => { escaped.push_str("&apos;") }
+            _ => { escaped.push(c) }
+        }
+    }
+    escaped
+}
+
+fn seconds(duration_millis: u64) -> String {
+    format!("{:.3}", duration_millis as f64 / 1000.0)
+}
+
+/// One test case per trace, a divergence, timeout or node crash is a failure, a protocol error
+/// is an error
+fn junit_report(results: &Vec<(TraceResult, TraceVerdict)>) -> String {
+    let num_failures = results.iter().filter(|(_, v)| {
+        matches!(v, TraceVerdict::Diverged { .. } | TraceVerdict::Timeout { .. }
+            | TraceVerdict::NodeCrashed { .. })
+    }).count();
+    let num_errors = results.iter().filter(|(_, v)| {
+        matches!(v, TraceVerdict::ProtocolError { .. })
+    }).count();
+    let total_millis: u64 = results.iter().map(|(r, _)| { r.duration_millis }).sum();
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\">\n",
+        results.len(), num_failures, num_errors, seconds(total_millis)).as_str());
+    xml.push_str(format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\">\n",
+        TEST_SUITE_NAME, results.len(), num_failures, num_errors, seconds(total_millis)).as_str());
+    for (result, verdict) in results {
+        xml.push_str(format!(
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{}\"",
+            xml_escape(result.trace_id.as_str()), TEST_SUITE_NAME,
+            seconds(result.duration_millis)).as_str());
+        if verdict.is_passed() {
+            xml.push_str("/>\n");
+            continue;
+        }
+        let tag = match verdict {
+            TraceVerdict::ProtocolError { .. } => { "error" }
+            _ => { "failure" }
+        };
+        let message = result.error.clone().unwrap_or_default();
+        xml.push_str(">\n");
+        xml.push_str(format!(
+            "      <{} type=\"{}\" message=\"{}\">{}</{}>\n",
+            tag, verdict.status(), xml_escape(message.as_str()),
+            xml_escape(verdict.to_string().as_str()), tag).as_str());
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n");
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// One JSON object per line per trace, the fields are stable:
+/// `trace_id`, `trace_index`, `status`, `duration_millis`, `timestamp`, `failing_step`,
+/// `failing_action`, `message`
+fn jsonl_report(results: &Vec<(TraceResult, TraceVerdict)>) -> String {
+    let mut lines = String::new();
+    for (result, verdict) in results {
+        lines.push_str(jsonl_line(result, verdict).as_str());
+    }
+    lines
+}
+
+fn jsonl_line(result: &TraceResult, verdict: &TraceVerdict) -> String {
+    let failing_action = verdict.expected().map(|a| { a.serde_json_value_ref().clone() });
+    let value = json!({
+        "trace_id": result.trace_id,
+        "trace_index": verdict.trace_index(),
+        "status": result.status,
+        "duration_millis": result.duration_millis,
+        "timestamp": result.timestamp,
+        "failing_step": verdict.step().map(|s| { s.to_string() }),
+        "failing_action": failing_action,
+        "message": result.error,
+    });
+    let mut line = value.to_string();
+    line.push('\n');
+    line
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{read_to_string, remove_file};
+
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+
+    use sedeve_kit::action::action_json::ActionJson;
+    use sedeve_kit::dtm::trace_verdict::TraceVerdict;
+    use sedeve_kit::trace::trace_db::TraceResult;
+    use sedeve_kit::util::tmp::tmp_dir;
+
+    use crate::report::{jsonl_report, junit_report, ReportFormat, ReportSpec, ReportWriter};
+
+    #[test]
+    fn test_report() {
+        assert_eq!(ReportSpec::parse("junit=a.xml").unwrap().format, ReportFormat::JUnit);
+        assert!(ReportSpec::parse("html=a.html").is_err());
+
+        let result = |id: &str, status: &str| {
+            TraceResult {
+                trace_id: id.to_string(),
+                status: status.to_string(),
+                failing_step: None,
+                duration_millis: 1500,
+                timestamp: 0,
+                error: Some("trace 1 <error>".to_string()),
+            }
+        };
+        let results = vec![
+            (result("t0", "passed"), TraceVerdict::Passed { trace_index: 0 }),
+            (result("t1", "protocol_error"), TraceVerdict::ProtocolError {
+                trace_index: 1,
+                step: None,
+                expected: Some(ActionJson::from_value(json!({"Input": {}}))),
+                message: "error".to_string(),
+            }),
+        ];
+        let xml = junit_report(&results);
+        assert!(xml.contains("<testcase name=\"t0\" classname=\"sedeve_player\" time=\"1.500\"/>"));
+        assert!(xml.contains("message=\"trace 1 &lt;error&gt;\""));
+
+        let jsonl = jsonl_report(&results);
+        let lines: Vec<Value> = jsonl.lines().map(|l| { serde_json::from_str(l).unwrap() }).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["status"], "protocol_error");
+        assert_eq!(lines[1]["failing_action"], json!({"Input": {}}));
+
+        let jsonl_path = tmp_dir(format!("report_{}.jsonl", Uuid::new_v4()));
+        let junit_path = tmp_dir(format!("report_{}.xml", Uuid::new_v4()));
+        let mut writer = ReportWriter::create(&[
+            ReportSpec::parse(format!("jsonl={}", jsonl_path).as_str()).unwrap(),
+            ReportSpec::parse(format!("junit={}", junit_path).as_str()).unwrap(),
+        ]).unwrap();
+        // a line is appended as each trace finishes, the JUnit report waits for the run to finish
+        writer.append(&results[1].0, &results[1].1).unwrap();
+        assert_eq!(read_to_string(&jsonl_path).unwrap().lines().count(), 1);
+        assert!(read_to_string(&junit_path).is_err());
+        writer.append(&results[0].0, &results[0].1).unwrap();
+        writer.finish(&results).unwrap();
+        assert_eq!(read_to_string(&jsonl_path).unwrap().lines().count(), 2);
+        assert_eq!(read_to_string(&junit_path).unwrap(), xml);
+        let _ = remove_file(jsonl_path);
+        let _ = remove_file(junit_path);
+    }
+}
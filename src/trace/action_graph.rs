@@ -3,9 +3,62 @@ use std::fmt::Debug;
 use std::hash::Hash;
 
 use scupt_util::res::Res;
-use tracing::{error, trace};
+use tracing::{error, trace, warn};
 
-use crate::trace::graph_find_path::graph_find_path;
+use crate::trace::graph_edge_cover::graph_edge_cover;
+use crate::trace::graph_find_path::{graph_find_path, reverse_adj};
+use crate::trace::graph_random_walk::graph_random_walk;
+use crate::trace::graph_scc_path::graph_scc_path;
+use crate::trace::path_filter::PathFilter;
+
+/// The strategy to generate the paths of an action graph
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Default)]
+pub enum PathStrategy {
+    /// The root-to-leaf paths found by depth first search
+    #[default]
+    Dfs,
+    /// A small set of root-to-leaf paths covering every reachable edge at least once, the edges
+    /// on cycles included
+    EdgeCover,
+    /// Random walks of bounded length from the initial states, reproducible for a seed
+    RandomWalk,
+    /// The root-to-leaf paths of the graph whose SCCs are contracted, each path walks through
+    /// the cycles of the SCCs it enters
+    Scc,
+}
+
+/// The options to generate the paths of an action graph
+#[derive(Clone, Debug)]
+pub struct OptPath {
+    pub strategy: PathStrategy,
+    /// the number of random walks
+    pub count: u64,
+    /// the max number of actions of a random walk
+    pub max_len: u64,
+    /// the seed of the random walks
+    pub seed: u64,
+    /// pick the successors of a random walk with weights favoring the edges less walked,
+    /// otherwise uniformly
+    pub weighted: bool,
+    /// the number of times a path walks the cycles of a SCC, for the SCC strategy
+    pub unroll: u64,
+    /// only the paths matched are output, each one is cut after its shortest prefix matched
+    pub filter: PathFilter,
+}
+
+impl Default for OptPath {
+    fn default() -> Self {
+        Self {
+            strategy: PathStrategy::default(),
+            count: 100,
+            max_len: 100,
+            seed: 0,
+            weighted: false,
+            unroll: 1,
+            filter: PathFilter::default(),
+        }
+    }
+}
 
 pub struct ActionGraph<
     K: Eq + Hash + Clone + Ord + Debug + ToString
@@ -46,6 +99,7 @@ ActionGraph<K> {
 
     pub fn build_path< FP>(
         &self,
+        opt: &OptPath,
         fn_handle_path: &FP,
     ) -> Res<()>
         where
@@ -53,7 +107,7 @@ ActionGraph<K> {
             FP: Fn(Vec<K>)
 
     {
-        let adj = self.adj.clone();
+        let adj = &self.adj;
         let fn_find_path = |v: Vec<K>| {
             trace!("find path {:?}", v);
             for i in 0..v.len() {
@@ -83,7 +137,29 @@ ActionGraph<K> {
             }
             fn_handle_path(v);
         };
-        graph_find_path(&self.adj, &fn_find_path);
+        match opt.strategy {
+            PathStrategy::Dfs => {
+                graph_find_path(&self.adj, &fn_find_path);
+            }
+            PathStrategy::EdgeCover => {
+                let unreachable = graph_edge_cover(&self.adj, &fn_find_path);
+                for (u, v) in unreachable.iter() {
+                    warn!("edge {:?} -> {:?} is unreachable from the roots", u, v);
+                }
+            }
+            PathStrategy::RandomWalk => {
+                // an edge of the graph points to the previous action, walk from the initial
+                // states along the reversed edges
+                let fn_walk = |v: Vec<K>| {
+                    fn_find_path(v.into_iter().rev().collect())
+                };
+                graph_random_walk(&reverse_adj(&self.adj), opt.count, opt.max_len, opt.seed,
+                                  opt.weighted, &fn_walk);
+            }
+            PathStrategy::Scc => {
+                graph_scc_path(&self.adj, opt.unroll, &fn_find_path);
+            }
+        }
 
         Ok(())
     }
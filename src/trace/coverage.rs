@@ -0,0 +1,231 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use scupt_util::error_type::ET;
+use scupt_util::res::Res;
+use serde::{Deserialize, Serialize};
+
+use crate::action::res_serde::res_serde;
+use crate::trace::trace_db::TraceDB;
+use crate::trace::trace_db_interm::TraceDBInterm;
+
+/// The traces counted in a coverage report
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageScope {
+    /// all the traces generated, whether run or not
+    Generated,
+    /// the traces run by the player
+    Executed,
+    /// the traces passed
+    Passed,
+}
+
+/// Which states and edges of the state graph are exercised by a set of traces.
+///
+/// An edge is a row (prev_id, id) of the `action` table, a trace covers the edges along its
+/// path in the `path` table.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub scope: CoverageScope,
+    pub num_traces: u64,
+    pub num_states: u64,
+    pub num_states_covered: u64,
+    pub num_edges: u64,
+    pub num_edges_covered: u64,
+    /// the fraction of edges covered
+    pub edge_coverage: f64,
+    /// the names of the actions, which are on some edges but on no edge covered
+    pub uncovered_action_names: Vec<String>,
+    /// the edges not covered, (prev_id, id)
+    pub uncovered_transitions: Vec<(i64, i64)>,
+}
+
+impl CoverageReport {
+    /// Compute the coverage of the traces in `scope`.
+    /// The results of the traces are read from `trace_db`, which is not required when the scope
+    /// is `Generated`.
+    pub fn new(
+        interm: &TraceDBInterm,
+        trace_db: Option<&TraceDB>,
+        scope: CoverageScope,
+    ) -> Res<Self> {
+        // edge into state id -> (prev_id, names of the actions on the edge)
+        let edges = interm.read_action_names()?;
+
+        let opt_selected: Option<HashSet<String>> = match scope {
+            CoverageScope::Generated => { None }
+            CoverageScope::Executed | CoverageScope::Passed => {
+                let db = match trace_db {
+                    Some(db) => { db }
+                    None => {
+                        return Err(ET::NoneOption);
+                    }
+                };
+                let ids = db.read_trace_result()?
+                    .into_iter()
+                    .filter(|r| { scope == CoverageScope::Executed || r.status == "passed" })
+                    .map(|r| { r.trace_id })
+                    .collect();
+                Some(ids)
+            }
+        };
+
+        let mut num_traces = 0;
+        let mut covered: HashSet<i64> = HashSet::new();
+        let mut f_path = |path_id: String, path: Vec<i64>| -> Res<()> {
+            if let Some(selected) = &opt_selected {
+                if !selected.contains(&path_id) {
+                    return Ok(());
+                }
+            }
+            num_traces += 1;
+            covered.extend(path);
+            Ok(())
+        };
+        interm.read_path(&mut f_path)?;
+        Ok(Self::from_edges(scope, num_traces, &edges, &covered))
+    }
+
+    fn from_edges(
+        scope: CoverageScope,
+        num_traces: u64,
+        edges: &HashMap<i64, (i64, Vec<String>)>,
+        covered: &HashSet<i64>,
+    ) -> Self {
+        let mut states = BTreeSet::new();
+        let mut states_covered = BTreeSet::new();
+        let mut names_covered = BTreeSet::new();
+        let mut names_uncovered = BTreeSet::new();
+        let mut uncovered_transitions = vec![];
+        for (id, (prev_id, names)) in edges.iter() {
+            states.insert(*id);
+            states.insert(*prev_id);
+            if covered.contains(id) {
+                states_covered.insert(*id);
+                states_covered.insert(*prev_id);
+                names_covered.extend(names.iter().cloned());
+            } else {
+                names_uncovered.extend(names.iter().cloned());
+                uncovered_transitions.push((*prev_id, *id));
+            }
+        }
+        // the previous state of an initial state may be not a state of the graph
+        states.retain(|id| { edges.contains_key(id) });
+        states_covered.retain(|id| { edges.contains_key(id) });
+        uncovered_transitions.sort();
+        let num_edges = edges.len() as u64;
+        let num_edges_covered = num_edges - uncovered_transitions.len() as u64;
+        let edge_coverage = if num_edges == 0 {
+            1.0
+        } else {
+            num_edges_covered as f64 / num_edges as f64
+        };
+        Self {
+            scope,
+            num_traces,
+            num_states: states.len() as u64,
+            num_states_covered: states_covered.len() as u64,
+            num_edges,
+            num_edges_covered,
+            edge_coverage,
+            uncovered_action_names: names_uncovered.difference(&names_covered).cloned().collect(),
+            uncovered_transitions,
+        }
+    }
+
+    pub fn to_json(&self) -> Res<String> {
+        res_serde(serde_json::to_string_pretty(self))
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "coverage of {} {:?} traces", self.num_traces, self.scope)?;
+        writeln!(f, "states covered: {}/{}", self.num_states_covered, self.num_states)?;
+        writeln!(f, "edges covered: {}/{}, {:.2}%",
+                 self.num_edges_covered, self.num_edges, self.edge_coverage * 100.0)?;
+        writeln!(f, "uncovered actions: {}", self.uncovered_action_names.len())?;
+        for name in self.uncovered_action_names.iter() {
+            writeln!(f, "  {}", name)?;
+        }
+        write!(f, "uncovered transitions(prev_id, id): {}", self.uncovered_transitions.len())?;
+        for (prev_id, id) in self.uncovered_transitions.iter() {
+            write!(f, "\n  ({}, {})", prev_id, id)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use serde_json::json;
+
+    use crate::trace::coverage::{CoverageReport, CoverageScope};
+    use crate::trace::trace_db::{TraceDB, TraceResult};
+    use crate::trace::trace_db_interm::TraceDBInterm;
+
+    fn actions(name: &str) -> String {
+        json!([{"Internal": {"source": 1, "dest": 1, "payload": {name: {"Step": 1}}}}]).to_string()
+    }
+
+    #[test]
+    fn test_coverage() {
+        let dir = std::env::temp_dir();
+        let interm_path = dir.join(format!("test_coverage_interm_{}.db", std::process::id()));
+        let trace_path = dir.join(format!("test_coverage_trace_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(interm_path.clone());
+        let _ = std::fs::remove_file(trace_path.clone());
+        let interm = TraceDBInterm::new(interm_path.to_str().unwrap().to_string(), None, None).unwrap();
+        // 1 -> 2 -> 3
+        //      2 -> 4
+        interm.write_action(vec![
+            (1, 1, "[]".to_string(), actions("Init")),
+            (2, 1, "[]".to_string(), actions("A")),
+            (3, 2, "[]".to_string(), actions("B")),
+            (4, 2, "[]".to_string(), actions("C")),
+        ]).unwrap();
+        // the paths are written from the last state
+        interm.write_path(vec![vec![3, 2, 1], vec![4, 2, 1]]).unwrap();
+        let paths = RefCell::new(vec![]);
+        interm.read_path(&mut |id, path| {
+            paths.borrow_mut().push((id, path));
+            Ok(())
+        }).unwrap();
+        let paths = paths.into_inner();
+        assert_eq!(paths.len(), 2);
+
+        let r = CoverageReport::new(&interm, None, CoverageScope::Generated).unwrap();
+        assert_eq!(r.num_traces, 2);
+        assert_eq!(r.num_edges_covered, 4);
+        assert!(r.uncovered_transitions.is_empty());
+
+        let db = TraceDB::new(trace_path.to_str().unwrap().to_string()).unwrap();
+        assert!(CoverageReport::new(&interm, None, CoverageScope::Passed).is_err());
+        let (id, path) = paths.iter().find(|(_, p)| { p.contains(&3) }).unwrap();
+        assert_eq!(*path, vec![1, 2, 3]);
+        db.write_trace_result(&TraceResult {
+            trace_id: id.clone(),
+            status: "passed".to_string(),
+            failing_step: None,
+            duration_millis: 1,
+            timestamp: 1,
+            error: None,
+        }).unwrap();
+        let r = CoverageReport::new(&interm, Some(&db), CoverageScope::Passed).unwrap();
+        assert_eq!(r.num_traces, 1);
+        assert_eq!(r.num_states, 4);
+        assert_eq!(r.num_states_covered, 3);
+        assert_eq!(r.num_edges_covered, 3);
+        assert_eq!(r.edge_coverage, 0.75);
+        assert_eq!(r.uncovered_action_names, vec!["C::Step".to_string()]);
+        assert_eq!(r.uncovered_transitions, vec![(2, 4)]);
+        assert!(r.to_string().contains("edges covered: 3/4, 75.00%"));
+        assert!(r.to_json().unwrap().contains("\"scope\": \"passed\""));
+
+        let _ = std::fs::remove_file(interm_path);
+        let _ = std::fs::remove_file(trace_path);
+    }
+}
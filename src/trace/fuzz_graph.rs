@@ -1,7 +1,7 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 use scupt_util::res::Res;
-use crate::trace::action_graph::ActionGraph;
+use crate::trace::action_graph::{ActionGraph, OptPath};
 
 
 #[allow(dead_code)]
@@ -13,7 +13,7 @@ fn build_graph(adj:&HashMap<i64, Vec<i64>>) -> Res<Vec<Vec<i64>>> {
         let mut r = path.borrow_mut();
         r.push(v);
     };
-    graph.build_path(&handle_path)?;
+    graph.build_path(&OptPath::default(), &handle_path)?;
     Ok(path.into_inner())
 }
 
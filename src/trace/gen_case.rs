@@ -6,8 +6,9 @@ use scupt_util::res::Res;
 use serde_json::Value;
 use tracing::{info, trace};
 
+
 use crate::trace::to_action::state_to_action;
-use crate::trace::action_graph::ActionGraph;
+use crate::trace::action_graph::{ActionGraph, OptPath};
 use crate::trace::trace_builder::{OptBuild, TraceBuilder};
 use crate::trace::trace_db_interm::{Stage, TraceDBInterm};
 
@@ -30,9 +31,9 @@ pub fn gen_case(
     data_output: String,
     dict: HashMap<String, Value>,
     opt_intermediate_path: Option<String>,
-    sqlite_cache_size: Option<u64>,
-    initialize_setup: bool,
+    opt_build: OptBuild,
     gen_trace:bool,
+    opt_path: OptPath,
 ) -> Res<()> {
     let intermediate = match opt_intermediate_path {
         Some(p) => { p }
@@ -40,6 +41,7 @@ pub fn gen_case(
             format!("{}.intermediate.db", data_output)
         }
     };
+    let sqlite_cache_size = opt_build.sqlite_cache_size;
     trace!("use const mapping: {:?}", dict);
     let inst = Instant::now();
     match data_input {
@@ -50,16 +52,12 @@ pub fn gen_case(
 
             if gen_trace {
                 let inst = Instant::now();
-                action_graph_output_to_db(&graph, intermediate.clone(), sqlite_cache_size)?;
+                action_graph_output_to_db(&graph, intermediate.clone(), sqlite_cache_size, &opt_path)?;
                 let duration = inst.elapsed();
                 info!("Time elapsed to generate path, time costs: {:?}",  duration);
 
                 let inst = Instant::now();
-                let opt = OptBuild {
-                    initialize_setup,
-                    sqlite_cache_size,
-                };
-                TraceBuilder::build(intermediate, data_output, opt)?;
+                TraceBuilder::build(intermediate, data_output, opt_build)?;
 
                 let duration = inst.elapsed();
                 info!("Time elapsed to gen final trace, time costs: {:?}", duration);
@@ -75,6 +73,7 @@ fn action_graph_output_to_db(
     action_graph: &ActionGraph<i64>,
     db_path: String,
     sqlite_cache_size: Option<u64>,
+    opt_path: &OptPath,
 ) -> Res<()> {
     let db = RefCell::new(TraceDBInterm::new(db_path, None, sqlite_cache_size)?);
     let write_path = {
@@ -90,6 +89,12 @@ fn action_graph_output_to_db(
     };
     if write_path {
         info!("To write path to DB");
+        let filter = &opt_path.filter;
+        let action_names = if filter.is_empty() {
+            HashMap::new()
+        } else {
+            db.borrow().read_action_names()?
+        };
         let vec: RefCell<Vec<Vec<i64>>> = RefCell::new(
             Vec::with_capacity(PATH_WRITE_BATCH));
 
@@ -101,6 +106,18 @@ fn action_graph_output_to_db(
             db_ref.write_path(batch).unwrap();
         };
         let f_write_path = |v: Vec<i64>| {
+            let v = if filter.is_empty() {
+                v
+            } else {
+                // a path is from the last state to the initial state
+                let steps: Vec<Vec<String>> = v.iter().rev()
+                    .map(|id| { action_names.get(id).map(|(_, names)| { names.clone() }).unwrap_or_default() })
+                    .collect();
+                match filter.shortest_match(&steps) {
+                    Some(n) => { v[v.len() - n..].to_vec() }
+                    None => { return; }
+                }
+            };
             let write_to_db = {
                 let mut vec_ref = vec.borrow_mut();
                 if !v.is_empty() {
@@ -115,7 +132,7 @@ fn action_graph_output_to_db(
         };
 
         let inst = Instant::now();
-        action_graph.build_path( &f_write_path)?;
+        action_graph.build_path(opt_path, &f_write_path)?;
         {
             write_batch_to_db();
         }
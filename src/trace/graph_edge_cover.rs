@@ -0,0 +1,175 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::Instant;
+
+use tracing::{info, trace};
+
+use crate::trace::graph_find_path::{reverse_adj, root_vertex};
+
+/// Find a small set of root-to-leaf paths, which cover every edge reachable from the roots at
+/// least once, the edges on cycles included.
+///
+/// Each path is a shortest path from a root to an uncovered edge, extended by uncovered edges as
+/// long as possible, and then by a shortest path to a leaf.
+/// A self-loop marks an initial state, which is not a transition to cover.
+/// Return the edges unreachable from the roots, which are in no path.
+pub fn graph_edge_cover<V, FP>(
+    adj: &HashMap<V, Vec<V>>,
+    fn_find_path: &FP,
+) -> Vec<(V, V)>
+    where
+        V: Eq + Hash + Clone + Ord + Debug,
+        FP: Fn(Vec<V>)
+{
+    if adj.is_empty() {
+        return vec![];
+    }
+    let start = Instant::now();
+    let mut roots: Vec<V> = root_vertex(adj).into_iter().collect();
+    roots.sort();
+    let from_root = bfs_tree(roots, adj);
+    let leaves: Vec<V> = adj.iter()
+        .filter(|(v, vec)| { vec.iter().all(|u| { u == *v }) })
+        .map(|(v, _)| { v.clone() })
+        .collect();
+    let to_leaf = bfs_tree(leaves, &reverse_adj(adj));
+
+    let mut vertices: Vec<&V> = adj.keys().collect();
+    vertices.sort();
+    let mut edges = vec![];
+    let mut unreachable = vec![];
+    for u in vertices {
+        for v in adj[u].iter().filter(|v| { *v != u }) {
+            if from_root.contains_key(u) {
+                edges.push((u.clone(), v.clone()));
+            } else {
+                unreachable.push((u.clone(), v.clone()));
+            }
+        }
+    }
+    let mut uncovered: HashSet<(V, V)> = edges.iter().cloned().collect();
+    let mut num_path = 0;
+    for (u, v) in edges {
+        if !uncovered.contains(&(u.clone(), v.clone())) {
+            continue;
+        }
+        let mut path = tree_path(&from_root, &u);
+        path.reverse();
+        path.push(v.clone());
+        for i in 1..path.len() {
+            uncovered.remove(&(path[i - 1].clone(), path[i].clone()));
+        }
+        let mut x = v;
+        while let Some(y) = adj[&x].iter().find(|y| { uncovered.contains(&(x.clone(), (*y).clone())) }) {
+            let y = y.clone();
+            uncovered.remove(&(x.clone(), y.clone()));
+            path.push(y.clone());
+            x = y;
+        }
+        let to = tree_path(&to_leaf, &x);
+        for i in 1..to.len() {
+            uncovered.remove(&(to[i - 1].clone(), to[i].clone()));
+        }
+        path.extend(to.into_iter().skip(1));
+        trace!("edge cover path : {:?}", path);
+        num_path += 1;
+        fn_find_path(path);
+    }
+    let duration = start.elapsed();
+    info!("Time elapsed to find {} paths covering edges : {:?}", num_path, duration);
+    unreachable
+}
+
+// the breadth first search tree from `sources`, the parent of each vertex reached, or None if it
+// is a source
+fn bfs_tree<V>(sources: Vec<V>, adj: &HashMap<V, Vec<V>>) -> HashMap<V, Option<V>>
+    where V: Eq + Hash + Clone + Ord + Debug
+{
+    let mut parent = HashMap::new();
+    let mut queue = VecDeque::new();
+    for s in sources {
+        if !parent.contains_key(&s) {
+            parent.insert(s.clone(), None);
+            queue.push_back(s);
+        }
+    }
+    while let Some(u) = queue.pop_front() {
+        if let Some(vec) = adj.get(&u) {
+            for v in vec.iter() {
+                if !parent.contains_key(v) {
+                    parent.insert(v.clone(), Some(u.clone()));
+                    queue.push_back(v.clone());
+                }
+            }
+        }
+    }
+    parent
+}
+
+// the path from `v` to the source of the tree
+fn tree_path<V>(tree: &HashMap<V, Option<V>>, v: &V) -> Vec<V>
+    where V: Eq + Hash + Clone
+{
+    let mut path = vec![v.clone()];
+    let mut x = v;
+    while let Some(Some(p)) = tree.get(x) {
+        path.push(p.clone());
+        x = p;
+    }
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::collections::{HashMap, HashSet};
+
+    use crate::data::path::_test::test_data_from_json;
+    use crate::trace::graph_edge_cover::graph_edge_cover;
+    use crate::trace::graph_find_path::root_vertex;
+    use crate::trace::graph_util::adj_add_new_edge;
+
+    fn edge_cover(adj: &HashMap<i64, Vec<i64>>) -> (Vec<Vec<i64>>, Vec<(i64, i64)>) {
+        let paths = RefCell::new(vec![]);
+        let unreachable = graph_edge_cover(adj, &|p| { paths.borrow_mut().push(p) });
+        (paths.into_inner(), unreachable)
+    }
+
+    #[test]
+    fn test_graph_edge_cover() {
+        let mut adj = HashMap::new();
+        // 1 is an initial state, 2 and 3 are on a cycle, 7 and 8 are on a cycle unreachable
+        for (u, v) in [(1, 1), (1, 2), (2, 3), (3, 2), (3, 4), (7, 8), (8, 7)] {
+            adj_add_new_edge(&mut adj, &u, &v);
+        }
+        let (paths, unreachable) = edge_cover(&adj);
+        assert_eq!(paths, vec![vec![1, 2, 3, 2, 3, 4]]);
+        assert_eq!(unreachable, vec![(7, 8), (8, 7)]);
+    }
+
+    #[test]
+    fn test_graph_edge_cover_fixture() {
+        let test_data = test_data_from_json::<HashMap<i64, Vec<i64>>, Vec<Vec<i64>>>(
+            "graph_find_path_1".to_string());
+        for (input, _, _) in test_data.iter() {
+            let mut adj = input.clone();
+            crate::trace::action_graph::ActionGraph::formalize_adj(&mut adj);
+            let roots = root_vertex(&adj);
+            let (paths, unreachable) = edge_cover(&adj);
+            let mut covered = HashSet::new();
+            for p in paths.iter() {
+                assert!(roots.contains(&p[0]));
+                for w in p.windows(2) {
+                    assert!(adj[&w[0]].contains(&w[1]));
+                    covered.insert((w[0], w[1]));
+                }
+            }
+            for (u, vec) in adj.iter() {
+                for v in vec.iter().filter(|v| { *v != u }) {
+                    assert!(covered.contains(&(*u, *v)) || unreachable.contains(&(*u, *v)));
+                }
+            }
+        }
+    }
+}
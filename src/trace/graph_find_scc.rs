@@ -45,10 +45,12 @@ pub fn graph_find_scc<
     sccs
 }
 
-// Recursive function to find the strongly connected component of a vertex using Tarjan's algorithm
-fn strong_connect<T: Eq + Hash + Clone + Ord + Debug>(
-    v: &T,
-    adj: &HashMap<T, Vec<T>>,
+// Find the strongly connected components reachable from vertex `root` using Tarjan's algorithm,
+// the recursion is replaced by an explicit call stack, the memory used grows with the depth of
+// the search
+fn strong_connect<'a, T: Eq + Hash + Clone + Ord + Debug>(
+    root: &'a T,
+    adj: &'a HashMap<T, Vec<T>>,
     index: &mut usize,
     stack: &mut VecDeque<T>,
     on_stack: &mut HashSet<T>,
@@ -56,65 +58,79 @@ fn strong_connect<T: Eq + Hash + Clone + Ord + Debug>(
     low_link: &mut HashMap<T, usize>,
     sccs: &mut Vec<HashMap<T, Vec<T>>>,
 ) {
-    indices.insert(v.clone(), *index);
-    low_link.insert(v.clone(), *index);
-    *index += 1;
-    stack.push_front(v.clone());
-    on_stack.insert(v.clone());
-
-    if let Some(neighbors) = adj.get(v) {
+    // The call stack, each vertex with the index of its next neighbor to visit
+    let mut call_stack: Vec<(&'a T, usize)> = Vec::new();
+    let mut opt_v = Some(root);
+    loop {
+        if let Some(v) = opt_v.take() {
+            indices.insert(v.clone(), *index);
+            low_link.insert(v.clone(), *index);
+            *index += 1;
+            stack.push_front(v.clone());
+            on_stack.insert(v.clone());
+            call_stack.push((v, 0));
+        }
+        let (v, i) = match call_stack.last_mut() {
+            Some(top) => { top }
+            None => { break; }
+        };
+        let v: &'a T = v;
+        let neighbors: &'a [T] = adj.get(v).map(|n| { n.as_slice() }).unwrap_or(&[]);
         // Iterate over each neighbor of the vertex v
-        for neighbor in neighbors {
-            // If the neighbor has not been visited yet, call the strong_connect recursively
+        if *i < neighbors.len() {
+            let neighbor = &neighbors[*i];
+            *i += 1;
             if !indices.contains_key(neighbor) {
-                strong_connect(neighbor, adj, index, stack, on_stack, indices, low_link, sccs);
-                let neighbor_low_link = low_link.get(neighbor).unwrap().clone();
-                let node_low_link = low_link.get_mut(v).unwrap();
-                *node_low_link = (*node_low_link).min(neighbor_low_link);
+                // If the neighbor has not been visited yet, visit it as a recursive call would
+                opt_v = Some(neighbor);
             } else if on_stack.contains(neighbor) {
                 // If the neighbor is on the stack, update the low link value of the node
-                let neighbor_index = indices.get(neighbor).unwrap();
+                let neighbor_index = indices[neighbor];
                 let node_low_link = low_link.get_mut(v).unwrap();
-                *node_low_link = (*node_low_link).min(*neighbor_index);
+                *node_low_link = (*node_low_link).min(neighbor_index);
             }
+            continue;
         }
-    }
-    // If the low link value of the vertex is equal to its index,
-    // it is the root of a strongly connected component
-    if low_link[v] == indices[v] {
-        // Create a new HashMap to store the strongly connected component
-        let mut scc = HashMap::new();
-        // Clone a copy of on_stack set.
-        let v_set = on_stack.clone();
-        loop {
+        let _ = call_stack.pop();
+        // If the low link value of the vertex is equal to its index,
+        // it is the root of a strongly connected component
+        if low_link[v] == indices[v] {
             // Pop nodes off the stack until the current vertex is reached
-            let u = stack.pop_front().unwrap();
-            // Remove the vertex from the set on the stack
-            on_stack.remove(&u);
-
-            // Get the neighbors of the vertex
-            let w_neighbors = if let Some(n) = adj.get(&u) {
-                n.clone()
-            } else {
-                vec![]
-            };
-
-            for n in &w_neighbors {
-                if v_set.contains(n) {
-                    // Add the node and its neighbors to the strongly connected component
-                    adj_add_new_edge(&mut scc, &u, n);
+            let mut members = HashSet::new();
+            loop {
+                let u = stack.pop_front().unwrap();
+                // Remove the vertex from the set on the stack
+                on_stack.remove(&u);
+                let is_v = u == *v;
+                members.insert(u);
+                if is_v {
+                    break;
                 }
             }
-            if !scc.contains_key(&u) {
-                scc.insert(u.clone(), vec![]);
-            }
-
-            if &u == v {
-                break;
+            // Create a new HashMap to store the strongly connected component
+            let mut scc = HashMap::new();
+            for u in members.iter() {
+                if let Some(n) = adj.get(u) {
+                    for w in n.iter() {
+                        if members.contains(w) {
+                            // Add the node and its neighbors to the strongly connected component
+                            adj_add_new_edge(&mut scc, u, w);
+                        }
+                    }
+                }
+                if !scc.contains_key(u) {
+                    scc.insert(u.clone(), vec![]);
+                }
             }
+            // Add the strongly connected component to the vector of components
+            sccs.push(scc);
+        }
+        // Update the low link value of the caller, as the return of a recursive call
+        if let Some((parent, _)) = call_stack.last() {
+            let v_low_link = low_link[v];
+            let parent_low_link = low_link.get_mut(*parent).unwrap();
+            *parent_low_link = (*parent_low_link).min(v_low_link);
         }
-        // Add the strongly connected component to the vector of components
-        sccs.push(scc);
     }
 }
 
@@ -212,4 +228,19 @@ mod test {
         };
         assert!(graph_list_equal(&sccs3, &expect3));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_deep() {
+        // a cycle deep enough to overflow the stack of a recursive search
+        let n = 200_000i64;
+        let mut graph = HashMap::new();
+        for i in 0..n {
+            graph.insert(i, vec![(i + 1) % n]);
+        }
+        graph.insert(n, vec![0]);
+        let sccs = graph_find_scc(&graph);
+        assert_eq!(sccs.len(), 2);
+        let scc = sccs.iter().find(|s| { s.len() > 1 }).unwrap();
+        assert_eq!(scc.len() as i64, n);
+    }
+}
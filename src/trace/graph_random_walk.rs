@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::Instant;
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use tracing::{info, trace};
+
+use crate::trace::graph_find_path::root_vertex;
+
+/// Generate `count` random walks, each starts at a root and has at most `max_len` vertices.
+///
+/// A walk picks a successor of the last vertex at random, and stops at a leaf. When `weighted`
+/// is enabled, a successor is picked with weight 1/(1+n), n is the number of times the edge has
+/// been walked, which spreads the walks over the edges, otherwise the successors are picked
+/// uniformly.
+/// A self-loop marks an initial state, which is not a step of a walk.
+/// The walks are the same for the same graph and `seed`.
+pub fn graph_random_walk<V, FP>(
+    adj: &HashMap<V, Vec<V>>,
+    count: u64,
+    max_len: u64,
+    seed: u64,
+    weighted: bool,
+    fn_find_path: &FP,
+)
+    where
+        V: Eq + Hash + Clone + Ord + Debug,
+        FP: Fn(Vec<V>)
+{
+    if adj.is_empty() || max_len == 0 {
+        return;
+    }
+    let start = Instant::now();
+    let mut roots: Vec<V> = root_vertex(adj).into_iter().collect();
+    roots.sort();
+    // the successors sorted, for the walks not depending on the order of the map
+    let successors: HashMap<&V, Vec<&V>> = adj.iter().map(|(u, vec)| {
+        let mut succ: Vec<&V> = vec.iter().filter(|v| { *v != u }).collect();
+        succ.sort();
+        (u, succ)
+    }).collect();
+    let mut walked: HashMap<(&V, &V), u64> = HashMap::new();
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..count {
+        let i = pick(&mut rng, roots.iter().map(|_| { 1.0 }));
+        let mut u = &roots[i];
+        let mut path = vec![u.clone()];
+        while (path.len() as u64) < max_len {
+            let succ = match successors.get(u) {
+                Some(succ) if !succ.is_empty() => { succ }
+                _ => { break; }
+            };
+            let i = pick(&mut rng, succ.iter().map(|v| {
+                if weighted {
+                    1.0 / (1.0 + *walked.get(&(u, *v)).unwrap_or(&0) as f64)
+                } else {
+                    1.0
+                }
+            }));
+            let v = succ[i];
+            *walked.entry((u, v)).or_default() += 1;
+            path.push(v.clone());
+            u = v;
+        }
+        trace!("random walk path : {:?}", path);
+        fn_find_path(path);
+    }
+    let duration = start.elapsed();
+    info!("Time elapsed to generate {} random walks : {:?}", count, duration);
+}
+
+// the index picked at random with the weights
+fn pick<I: IntoIterator<Item=f64>>(rng: &mut StdRng, weights: I) -> usize {
+    let dist = WeightedIndex::new(weights).unwrap();
+    dist.sample(rng)
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    use crate::trace::graph_random_walk::graph_random_walk;
+    use crate::trace::graph_util::adj_add_new_edge;
+
+    fn random_walk(adj: &HashMap<i64, Vec<i64>>, max_len: u64, seed: u64, weighted: bool) -> Vec<Vec<i64>> {
+        let paths = RefCell::new(vec![]);
+        graph_random_walk(adj, 20, max_len, seed, weighted, &|p| { paths.borrow_mut().push(p) });
+        paths.into_inner()
+    }
+
+    #[test]
+    fn test_graph_random_walk() {
+        let mut adj = HashMap::new();
+        // 1 is an initial state, 2 and 3 are on a cycle, 4 and 5 are leaves
+        for (u, v) in [(1, 1), (1, 2), (2, 3), (3, 2), (3, 4), (2, 5)] {
+            adj_add_new_edge(&mut adj, &u, &v);
+        }
+        for weighted in [false, true] {
+            let paths = random_walk(&adj, 6, 7, weighted);
+            assert_eq!(paths.len(), 20);
+            for p in paths.iter() {
+                assert_eq!(p[0], 1);
+                assert!(p.len() <= 6);
+                assert!(p.len() == 6 || *p.last().unwrap() == 4 || *p.last().unwrap() == 5);
+                for w in p.windows(2) {
+                    assert!(adj[&w[0]].contains(&w[1]) && w[0] != w[1]);
+                }
+            }
+            // reproducible for a seed
+            assert_eq!(paths, random_walk(&adj, 6, 7, weighted));
+        }
+        assert_ne!(random_walk(&adj, 6, 7, false), random_walk(&adj, 6, 8, false));
+    }
+}
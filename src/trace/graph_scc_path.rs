@@ -0,0 +1,321 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::Instant;
+
+use tracing::{info, trace};
+
+use crate::trace::graph_find_scc::graph_find_scc;
+
+/// Find root-to-leaf paths which go through the cycles of the graph.
+///
+/// Each non-trivial strongly connected component(SCC) is contracted into one vertex, which makes
+/// the graph a DAG, and the paths of the DAG are found by depth first search.
+/// A path of the DAG is expanded to a path of the graph, when entering a non-trivial SCC, the path
+/// walks `unroll` times along a closed walk covering every edge of the SCC, and then leaves the
+/// SCC by a shortest path to the next component.
+/// A self-loop marks an initial state, which is not a transition to walk.
+pub fn graph_scc_path<V, FP>(
+    adj: &HashMap<V, Vec<V>>,
+    unroll: u64,
+    fn_find_path: &FP,
+)
+    where
+        V: Eq + Hash + Clone + Ord + Debug,
+        FP: Fn(Vec<V>)
+{
+    if adj.is_empty() {
+        return;
+    }
+    let start = Instant::now();
+    let condensed = Condensed::new(adj);
+    let duration = start.elapsed();
+    info!("Time elapsed to contract {} SCC: {:?}", condensed.scc.len(), duration);
+
+    let start = Instant::now();
+    let mut num_path = 0;
+    let fn_component_path = |components: &Vec<V>| {
+        let path = condensed.expand(adj, components, unroll);
+        trace!("SCC path : {:?}", path);
+        fn_find_path(path);
+    };
+    let mut visited = HashSet::new();
+    for root in condensed.roots() {
+        num_path += condensed.dfs_component_path(root, &mut visited, &fn_component_path);
+    }
+    let duration = start.elapsed();
+    info!("Time elapsed to find {} paths through SCC : {:?}", num_path, duration);
+}
+
+// the graph in which each SCC is contracted into one vertex, a component is named by its least
+// vertex
+struct Condensed<V: Eq + Hash + Clone + Ord + Debug> {
+    // the component of each vertex
+    component: HashMap<V, V>,
+    // the adjacent components of each component
+    adj: BTreeMap<V, BTreeSet<V>>,
+    // the edges of each SCC, the self-loops excluded
+    scc: HashMap<V, HashMap<V, Vec<V>>>,
+    // the closed covering walk of each SCC, which starts from the least vertex
+    walks: RefCell<HashMap<V, Vec<V>>>,
+}
+
+impl<V: Eq + Hash + Clone + Ord + Debug> Condensed<V> {
+    fn new(adj: &HashMap<V, Vec<V>>) -> Self {
+        let mut component = HashMap::new();
+        let mut scc = HashMap::new();
+        for s in graph_find_scc(adj) {
+            let name = s.keys().min().unwrap().clone();
+            for v in s.keys() {
+                component.insert(v.clone(), name.clone());
+            }
+            let s: HashMap<V, Vec<V>> = s.into_iter().map(|(u, vec)| {
+                let mut vec: Vec<V> = vec.into_iter().filter(|v| { *v != u }).collect();
+                vec.sort();
+                (u, vec)
+            }).collect();
+            scc.insert(name, s);
+        }
+        let mut c_adj: BTreeMap<V, BTreeSet<V>> = BTreeMap::new();
+        for (u, vec) in adj.iter() {
+            let cu = &component[u];
+            let set = c_adj.entry(cu.clone()).or_default();
+            for v in vec.iter() {
+                let cv = &component[v];
+                if cu != cv {
+                    set.insert(cv.clone());
+                }
+            }
+        }
+        Self {
+            component,
+            adj: c_adj,
+            scc,
+            walks: Default::default(),
+        }
+    }
+
+    // the components without incoming edges
+    fn roots(&self) -> Vec<&V> {
+        let mut has_in = HashSet::new();
+        for set in self.adj.values() {
+            has_in.extend(set.iter());
+        }
+        self.adj.keys().filter(|c| { !has_in.contains(c) }).collect()
+    }
+
+    // depth first search the paths of components from `root`, a path ends at a leaf of the
+    // search tree, which is extended to a leaf of the graph when the component has successors
+    // visited
+    fn dfs_component_path<FC>(&self, root: &V, visited: &mut HashSet<V>, fn_component_path: &FC) -> u64
+        where FC: Fn(&Vec<V>)
+    {
+        if !visited.insert(root.clone()) {
+            return 0;
+        }
+        let mut num_path = 0;
+        let mut path = vec![root.clone()];
+        let mut expanded = vec![false];
+        let mut iter_stack = vec![self.adj[root].iter()];
+        while let Some(iter) = iter_stack.last_mut() {
+            match iter.find(|c| { !visited.contains(*c) }) {
+                Some(c) => {
+                    visited.insert(c.clone());
+                    *expanded.last_mut().unwrap() = true;
+                    path.push(c.clone());
+                    expanded.push(false);
+                    iter_stack.push(self.adj[c].iter());
+                }
+                None => {
+                    if !expanded.pop().unwrap() {
+                        let mut p = path.clone();
+                        while let Some(c) = self.adj[p.last().unwrap()].iter().next() {
+                            p.push(c.clone());
+                        }
+                        num_path += 1;
+                        fn_component_path(&p);
+                    }
+                    let _ = path.pop();
+                    let _ = iter_stack.pop();
+                }
+            }
+        }
+        num_path
+    }
+
+    // expand a path of components to a path of the graph
+    fn expand(&self, adj: &HashMap<V, Vec<V>>, components: &[V], unroll: u64) -> Vec<V> {
+        // a component is entered at its least vertex at first
+        let mut x = components[0].clone();
+        let mut path = vec![x.clone()];
+        for (i, c) in components.iter().enumerate() {
+            let scc = &self.scc[c];
+            if scc.len() > 1 {
+                let mut walks = self.walks.borrow_mut();
+                let walk = walks.entry(c.clone()).or_insert_with(|| { scc_covering_walk(scc, c) });
+                // the closed walk visits every vertex of the SCC, rotate it to start from x
+                let k = walk.iter().position(|v| { *v == x }).unwrap();
+                for _ in 0..unroll {
+                    path.extend(walk[k + 1..].iter().chain(walk[1..=k].iter()).cloned());
+                }
+            }
+            if let Some(next) = components.get(i + 1) {
+                let is_exit = |v: &V| {
+                    adj[v].iter().any(|w| { self.component[w] == *next })
+                };
+                let p = bfs_path(scc, &x, &is_exit).unwrap();
+                x = p.last().unwrap().clone();
+                path.extend(p.into_iter().skip(1));
+                let y = adj[&x].iter().filter(|w| { self.component[*w] == *next }).min().unwrap();
+                x = y.clone();
+                path.push(x.clone());
+            }
+        }
+        path
+    }
+}
+
+// a closed walk from `start` covering every edge of a SCC
+fn scc_covering_walk<V>(scc: &HashMap<V, Vec<V>>, start: &V) -> Vec<V>
+    where V: Eq + Hash + Clone + Ord + Debug
+{
+    let num_edges: usize = scc.values().map(|vec| { vec.len() }).sum();
+    let mut covered: HashSet<(V, V)> = HashSet::new();
+    // the index of the first uncovered edge out of each vertex
+    let mut next: HashMap<V, usize> = scc.keys().map(|u| { (u.clone(), 0) }).collect();
+    let mut walk = vec![start.clone()];
+    let mut x = start.clone();
+    while covered.len() < num_edges {
+        let has_uncovered = |u: &V| { next[u] < scc[u].len() };
+        // a shortest path to an uncovered edge, and the edge
+        let mut p = match bfs_path(scc, &x, &has_uncovered) {
+            Some(p) => { p }
+            None => { break; }
+        };
+        let u = p.last().unwrap().clone();
+        p.push(scc[&u][next[&u]].clone());
+        for i in 1..p.len() {
+            cover_edge(scc, &mut covered, &mut next, &p[i - 1], &p[i]);
+        }
+        x = p.last().unwrap().clone();
+        walk.extend(p.into_iter().skip(1));
+    }
+    if let Some(p) = bfs_path(scc, &x, &|v: &V| { v == start }) {
+        walk.extend(p.into_iter().skip(1));
+    }
+    walk
+}
+
+// mark edge (u, v) covered, and move to the next uncovered edge out of u
+fn cover_edge<V>(
+    scc: &HashMap<V, Vec<V>>,
+    covered: &mut HashSet<(V, V)>,
+    next: &mut HashMap<V, usize>,
+    u: &V,
+    v: &V,
+) where V: Eq + Hash + Clone
+{
+    let _ = covered.insert((u.clone(), v.clone()));
+    let i = next.get_mut(u).unwrap();
+    while *i < scc[u].len() && covered.contains(&(u.clone(), scc[u][*i].clone())) {
+        *i += 1;
+    }
+}
+
+// a shortest path from `from` to a vertex satisfying `is_target`, in `adj`
+fn bfs_path<V, FT>(adj: &HashMap<V, Vec<V>>, from: &V, is_target: &FT) -> Option<Vec<V>>
+    where V: Eq + Hash + Clone + Ord + Debug,
+          FT: Fn(&V) -> bool
+{
+    let mut parent: HashMap<V, Option<V>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    parent.insert(from.clone(), None);
+    queue.push_back(from.clone());
+    while let Some(u) = queue.pop_front() {
+        if is_target(&u) {
+            let mut path = vec![u.clone()];
+            let mut x = &u;
+            while let Some(Some(p)) = parent.get(x) {
+                path.push(p.clone());
+                x = p;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if let Some(vec) = adj.get(&u) {
+            for v in vec.iter() {
+                if !parent.contains_key(v) {
+                    parent.insert(v.clone(), Some(u.clone()));
+                    queue.push_back(v.clone());
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::collections::{HashMap, HashSet};
+
+    use crate::data::path::_test::test_data_from_json;
+    use crate::trace::action_graph::ActionGraph;
+    use crate::trace::graph_find_scc::graph_find_scc;
+    use crate::trace::graph_scc_path::graph_scc_path;
+
+    fn scc_path(adj: &HashMap<i64, Vec<i64>>, unroll: u64) -> Vec<Vec<i64>> {
+        let paths = RefCell::new(vec![]);
+        graph_scc_path(adj, unroll, &|p| { paths.borrow_mut().push(p) });
+        paths.into_inner()
+    }
+
+    #[test]
+    fn test_graph_scc_path() {
+        let test_data = test_data_from_json::<(HashMap<i64, Vec<i64>>, u64), Vec<Vec<i64>>>(
+            "graph_scc_path_1".to_string());
+        assert!(!test_data.is_empty());
+        for ((input, unroll), result, p) in test_data.iter() {
+            let mut adj = input.clone();
+            ActionGraph::formalize_adj(&mut adj);
+            let mut paths = scc_path(&adj, *unroll);
+            let mut expected = result.clone();
+            paths.sort();
+            expected.sort();
+            assert_eq!(paths, expected, "test data {}", p);
+        }
+    }
+
+    #[test]
+    fn test_graph_scc_path_cover_cycle() {
+        let test_data = test_data_from_json::<HashMap<i64, Vec<i64>>, Vec<Vec<i64>>>(
+            "graph_find_path_1".to_string());
+        for (input, _, _) in test_data.iter() {
+            let mut adj = input.clone();
+            ActionGraph::formalize_adj(&mut adj);
+            for unroll in 0..3 {
+                let paths = scc_path(&adj, unroll);
+                assert!(!paths.is_empty());
+                let mut covered = HashSet::new();
+                for p in paths.iter() {
+                    assert!(adj[p.last().unwrap()].iter().all(|v| { v == p.last().unwrap() }));
+                    for w in p.windows(2) {
+                        assert!(adj[&w[0]].contains(&w[1]));
+                        covered.insert((w[0], w[1]));
+                    }
+                }
+                if unroll > 0 {
+                    // every edge of a cycle is walked
+                    for scc in graph_find_scc(&adj).iter().filter(|s| { s.len() > 1 }) {
+                        for (u, vec) in scc.iter() {
+                            for v in vec.iter().filter(|v| { *v != u }) {
+                                assert!(covered.contains(&(*u, *v)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -6,11 +6,12 @@ pub mod read_json;
 pub mod action_graph;
 
 mod graph_find_path;
+mod graph_edge_cover;
+mod graph_random_walk;
+mod graph_scc_path;
 
 mod graph_util;
 mod graph_find_scc;
-mod cover_walk_path;
-mod subgraph_path;
 mod test_trace_reader;
 pub mod to_json_value;
 pub mod to_action;
@@ -19,4 +20,9 @@ pub mod trace_db_interm;
 pub mod trace_db;
 pub mod fuzz_graph;
 pub mod trace_gen_portal;
+pub mod spec_graph;
+pub mod trace_validation;
+pub mod coverage;
+pub mod path_filter;
 
+pub mod trace_diagram;
@@ -0,0 +1,122 @@
+use regex::Regex;
+use scupt_util::error_type::ET;
+use scupt_util::res::Res;
+
+/// Select the paths which perform some actions, the patterns are regular expressions matching
+/// the whole name of an action, such as `Raft::StepDown`.
+#[derive(Clone, Debug, Default)]
+pub struct PathFilter {
+    // each pattern is matched by an action of the path
+    require: Vec<Regex>,
+    // no action of the path matches any pattern
+    forbid: Vec<Regex>,
+    // an action matching the first pattern is followed by an action matching the second one
+    sequence: Vec<(Regex, Regex)>,
+}
+
+impl PathFilter {
+    /// A sequence is a pair of patterns, an action matching the first one is followed by an
+    /// action matching the second one
+    pub fn new(require: &[String], forbid: &[String], sequence: &[(String, String)]) -> Res<Self> {
+        let mut s = Self::default();
+        for p in require {
+            s.require.push(name_regex(p)?);
+        }
+        for p in forbid {
+            s.forbid.push(name_regex(p)?);
+        }
+        for (a, b) in sequence {
+            s.sequence.push((name_regex(a)?, name_regex(b)?));
+        }
+        Ok(s)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.require.is_empty() && self.forbid.is_empty() && self.sequence.is_empty()
+    }
+
+    /// The number of steps of the shortest prefix of a path which matches, or None if no prefix
+    /// matches.
+    /// A step is the names of the actions from a state to the next one, the whole path is
+    /// matched when nothing is required.
+    pub fn shortest_match(&self, steps: &[Vec<String>]) -> Option<usize> {
+        let nothing_required = self.require.is_empty() && self.sequence.is_empty();
+        let mut required = vec![false; self.require.len()];
+        // 0: the first action not found, 1: the second action not found, 2: found both
+        let mut sequence = vec![0; self.sequence.len()];
+        for (i, names) in steps.iter().enumerate() {
+            for name in names.iter() {
+                if self.forbid.iter().any(|r| { r.is_match(name) }) {
+                    return None;
+                }
+                for (j, r) in self.require.iter().enumerate() {
+                    required[j] = required[j] || r.is_match(name);
+                }
+                for (j, (a, b)) in self.sequence.iter().enumerate() {
+                    if sequence[j] == 1 && b.is_match(name) {
+                        sequence[j] = 2;
+                    } else if sequence[j] == 0 && a.is_match(name) {
+                        sequence[j] = 1;
+                    }
+                }
+            }
+            if !nothing_required && required.iter().all(|b| { *b }) && sequence.iter().all(|n| { *n == 2 }) {
+                return Some(i + 1);
+            }
+        }
+        if nothing_required {
+            Some(steps.len())
+        } else {
+            None
+        }
+    }
+}
+
+fn name_regex(pattern: &str) -> Res<Regex> {
+    match Regex::new(format!("^(?:{})$", pattern).as_str()) {
+        Ok(r) => { Ok(r) }
+        Err(e) => { Err(ET::ParseError(e.to_string())) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::trace::path_filter::PathFilter;
+
+    fn steps(names: &[&str]) -> Vec<Vec<String>> {
+        names.iter().map(|n| { vec![n.to_string()] }).collect()
+    }
+
+    #[test]
+    fn test_path_filter() {
+        let path = steps(&["Init", "Raft::Vote", "Raft::BecomeLeader", "Raft::StepDown", "Raft::Vote"]);
+        let s = |v: &[&str]| -> Vec<String> { v.iter().map(|n| { n.to_string() }).collect() };
+
+        assert_eq!(PathFilter::default().shortest_match(&path), Some(5));
+        let f = PathFilter::new(&s(&["Raft::StepDown"]), &[], &[]).unwrap();
+        assert_eq!(f.shortest_match(&path), Some(4));
+        // match the whole name
+        let f = PathFilter::new(&s(&["StepDown"]), &[], &[]).unwrap();
+        assert_eq!(f.shortest_match(&path), None);
+        let f = PathFilter::new(&s(&["Raft::.*Leader", "Raft::Vote"]), &[], &[]).unwrap();
+        assert_eq!(f.shortest_match(&path), Some(3));
+
+        let seq = |a: &str, b: &str| -> Vec<(String, String)> { vec![(a.to_string(), b.to_string())] };
+        let f = PathFilter::new(&[], &[], &seq("Raft::StepDown", "Raft::Vote")).unwrap();
+        assert_eq!(f.shortest_match(&path), Some(5));
+        let f = PathFilter::new(&[], &[], &seq("Raft::StepDown", "Raft::BecomeLeader")).unwrap();
+        assert_eq!(f.shortest_match(&path), None);
+        // a pattern containing `..`
+        let f = PathFilter::new(&[], &[], &seq("Raft::Vote", "Raft::..epDown")).unwrap();
+        assert_eq!(f.shortest_match(&path), Some(4));
+
+        // a forbidden action before the required ones are found
+        let f = PathFilter::new(&s(&["Raft::StepDown"]), &s(&["Raft::BecomeLeader"]), &[]).unwrap();
+        assert_eq!(f.shortest_match(&path), None);
+        let f = PathFilter::new(&[], &s(&["Raft::Append.*"]), &[]).unwrap();
+        assert_eq!(f.shortest_match(&path), Some(5));
+
+        assert!(PathFilter::new(&[], &[], &seq("Raft::Vote", ")")).is_err());
+        assert!(PathFilter::new(&s(&["("]), &[], &[]).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use scupt_util::res::Res;
+use serde_json::Value;
+
+use crate::action::action_json::ActionJson;
+use crate::trace::trace_db_interm::TraceDBInterm;
+
+/// The state graph of a specification, which is loaded from the `action` table of the
+/// intermediate database.
+///
+/// A row (id, prev_id, action_json) is an edge from state `prev_id` to state `id`, labeled by
+/// the sequence of actions in `action_json`.
+/// A state is an initial state if its `prev_id` is itself or is not a state in the table.
+pub struct SpecGraph {
+    // the actions on the edge into a state
+    actions: HashMap<i64, Vec<ActionJson>>,
+    // the next states of a state
+    next: HashMap<i64, Vec<i64>>,
+    initial: Vec<i64>,
+}
+
+/// A state and the number of actions on the edge into it, which have been performed
+type Position = (i64, usize);
+
+/// Track the states of the specification, which are consistent with the sequence of actions
+/// performed so far
+#[derive(Clone)]
+pub struct SpecCursor {
+    graph: Arc<SpecGraph>,
+    positions: BTreeSet<Position>,
+}
+
+impl SpecGraph {
+    /// Build the graph from the rows, (id, prev_id, actions)
+    pub fn new(rows: Vec<(i64, i64, Vec<ActionJson>)>) -> Self {
+        let ids: HashSet<i64> = rows.iter().map(|(id, _, _)| { *id }).collect();
+        let mut actions = HashMap::new();
+        let mut next: HashMap<i64, Vec<i64>> = HashMap::new();
+        let mut initial = vec![];
+        for (id, prev_id, vec) in rows {
+            if Self::is_initial(id, prev_id, &ids) {
+                initial.push(id);
+            } else {
+                next.entry(prev_id).or_default().push(id);
+            }
+            actions.insert(id, vec);
+        }
+        initial.sort();
+        for v in next.values_mut() {
+            v.sort();
+        }
+        Self {
+            actions,
+            next,
+            initial,
+        }
+    }
+
+    /// Load the graph from the intermediate database at `path`.
+    /// A trace begins with the states of its initial state, so these states are the first
+    /// actions of an initial state.
+    pub fn from_db(path: String) -> Res<Self> {
+        let db = TraceDBInterm::new(path, None, None)?;
+        let mut rows = vec![];
+        let mut f = |id: i64, prev_id: i64, states: Vec<Value>, actions: Vec<Value>| -> Res<()> {
+            let states: Vec<ActionJson> = states.into_iter().map(ActionJson::from_value).collect();
+            let actions: Vec<ActionJson> = actions.into_iter().map(ActionJson::from_value).collect();
+            rows.push((id, prev_id, states, actions));
+            Ok(())
+        };
+        db.read_action(&mut f)?;
+        let ids: HashSet<i64> = rows.iter().map(|(id, _, _, _)| { *id }).collect();
+        let rows = rows.into_iter().map(|(id, prev_id, states, actions)| {
+            if Self::is_initial(id, prev_id, &ids) {
+                let mut vec = states;
+                vec.extend(actions);
+                (id, prev_id, vec)
+            } else {
+                (id, prev_id, actions)
+            }
+        }).collect();
+        Ok(Self::new(rows))
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.actions.len()
+    }
+
+    fn is_initial(id: i64, prev_id: i64, ids: &HashSet<i64>) -> bool {
+        prev_id == id || !ids.contains(&prev_id)
+    }
+
+    fn edge_actions(&self, id: i64) -> &[ActionJson] {
+        self.actions.get(&id).map_or(&[], |v| { v.as_slice() })
+    }
+
+    fn next_states(&self, id: i64) -> &[i64] {
+        self.next.get(&id).map_or(&[], |v| { v.as_slice() })
+    }
+}
+
+impl SpecCursor {
+    /// A cursor at the initial states of the graph
+    pub fn new(graph: Arc<SpecGraph>) -> Self {
+        let initial = graph.initial.iter().map(|id| { (*id, 0) }).collect();
+        let positions = Self::closure(&graph, initial);
+        Self {
+            graph,
+            positions,
+        }
+    }
+
+    /// The actions which can be performed next, one for each outgoing edge of the current states
+    pub fn enabled(&self) -> Vec<ActionJson> {
+        let mut vec: Vec<ActionJson> = vec![];
+        for (id, offset) in self.positions.iter() {
+            if let Some(a) = self.graph.edge_actions(*id).get(*offset) {
+                if !vec.contains(a) {
+                    vec.push(a.clone());
+                }
+            }
+        }
+        vec
+    }
+
+    /// Move the cursor along the edges labeled by `action`.
+    /// Return false and keep the cursor unchanged if no edge matches.
+    pub fn step(&mut self, action: &ActionJson) -> bool {
+        let mut matched = BTreeSet::new();
+        for (id, offset) in self.positions.iter() {
+            if self.graph.edge_actions(*id).get(*offset) == Some(action) {
+                matched.insert((*id, *offset + 1));
+            }
+        }
+        if matched.is_empty() {
+            return false;
+        }
+        self.positions = Self::closure(&self.graph, matched);
+        true
+    }
+
+    /// The states reached, all the actions on the edges into which have been performed
+    pub fn states(&self) -> Vec<i64> {
+        let mut vec: Vec<i64> = self.positions.iter()
+            .filter(|(id, offset)| { *offset >= self.graph.edge_actions(*id).len() })
+            .map(|(id, _)| { *id })
+            .collect();
+        vec.dedup();
+        vec
+    }
+
+    /// No action can be performed, all the states tracked are final states of the specification
+    pub fn is_final(&self) -> bool {
+        self.enabled().is_empty()
+    }
+
+    // a position at the end of an edge is also at the begin of the edges out of the state
+    fn closure(graph: &SpecGraph, positions: BTreeSet<Position>) -> BTreeSet<Position> {
+        let mut result = BTreeSet::new();
+        let mut stack: Vec<Position> = positions.into_iter().collect();
+        while let Some(pos) = stack.pop() {
+            if !result.insert(pos) {
+                continue;
+            }
+            let (id, offset) = pos;
+            if offset >= graph.edge_actions(id).len() {
+                for n in graph.next_states(id) {
+                    stack.push((*n, 0));
+                }
+            }
+        }
+        result
+    }
+}
+
+impl PartialEq for SpecCursor {
+    fn eq(&self, other: &Self) -> bool {
+        self.positions == other.positions
+    }
+}
+
+impl Eq for SpecCursor {}
+
+impl Hash for SpecCursor {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.positions.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use crate::action::action_json::ActionJson;
+    use crate::trace::spec_graph::{SpecCursor, SpecGraph};
+
+    fn action(name: &str) -> ActionJson {
+        ActionJson::from_value(json!({
+            "Internal": {"source": 1, "dest": 1, "payload": {name: 1}}
+        }))
+    }
+
+    #[test]
+    fn test_spec_graph() {
+        // 1 -a-> 2 -b,c-> 3
+        //        2 -b,d-> 4
+        // 1 -e-> 5 -(no action)-> 6 -f-> 7
+        let graph = SpecGraph::new(vec![
+            (1, 1, vec![]),
+            (2, 1, vec![action("a")]),
+            (3, 2, vec![action("b"), action("c")]),
+            (4, 2, vec![action("b"), action("d")]),
+            (5, 1, vec![action("e")]),
+            (6, 5, vec![]),
+            (7, 6, vec![action("f")]),
+        ]);
+        assert_eq!(graph.num_states(), 7);
+        let graph = Arc::new(graph);
+
+        let mut cursor = SpecCursor::new(graph.clone());
+        assert_eq!(cursor.enabled(), vec![action("a"), action("e")]);
+        assert!(!cursor.step(&action("b")));
+        assert!(cursor.step(&action("a")));
+        assert!(cursor.step(&action("b")));
+        assert_eq!(cursor.enabled(), vec![action("c"), action("d")]);
+        assert!(cursor.step(&action("d")));
+        assert_eq!(cursor.states(), vec![4]);
+        assert!(cursor.is_final());
+
+        let mut cursor = SpecCursor::new(graph);
+        assert!(cursor.step(&action("e")));
+        assert_eq!(cursor.enabled(), vec![action("f")]);
+        assert!(cursor.step(&action("f")));
+        assert!(cursor.is_final());
+    }
+}
@@ -4,8 +4,10 @@ mod test {
     use uuid::Uuid;
 
     use crate::data::path::_test::_test_data_path;
+    use crate::trace::action_graph::OptPath;
     use crate::trace::gen_case::{DataInput, gen_case};
     use crate::trace::read_json::tla_constant_mapping;
+    use crate::trace::trace_builder::OptBuild;
     use crate::trace::trace_reader::TraceReader;
     use crate::util::tmp::tmp_dir;
 
@@ -36,9 +38,12 @@ mod test {
                  output.clone(),
                  dict,
                  None,
-                 None,
-                 setup_initialize,
+                 OptBuild {
+                     initialize_setup: setup_initialize,
+                     sqlite_cache_size: None,
+                 },
                 true,
+                 OptPath::default(),
         ).unwrap();
         let vec = TraceReader::read_trace(output).unwrap();
         assert_eq!(vec.len(), expected_size);
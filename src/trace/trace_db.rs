@@ -2,9 +2,39 @@ use std::sync::Mutex;
 
 use rusqlite::Connection;
 use scupt_util::res::Res;
-use scupt_util::res_of::res_sqlite;
+use scupt_util::res_of::{res_parse, res_sqlite};
 use serde_json::Value;
 
+use crate::action::action_json::ActionJson;
+
+/// The result of running a trace
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceResult {
+    pub trace_id: String,
+    /// passed, diverged, timeout or protocol_error
+    pub status: String,
+    /// the sequence number of the failing step, start from 1
+    pub failing_step: Option<u64>,
+    pub duration_millis: u64,
+    /// seconds since the Unix epoch when the trace finished
+    pub timestamp: u64,
+    pub error: Option<String>,
+}
+
+/// An action reported by a tested node, which is recorded by the player in record mode
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedAction {
+    pub run_id: String,
+    /// the arrival order in the run, start from 1
+    pub seq: u64,
+    /// microseconds since the Unix epoch when the player received the action
+    pub timestamp_micros: u64,
+    /// the node which reported the action
+    pub node_id: u64,
+    pub begin: bool,
+    pub action: Value,
+}
+
 /// sqlite database stores trace
 pub struct TraceDB {
     _path: String,
@@ -24,6 +54,27 @@ impl TraceDB {
                         trace_json text not null
                         )"#, ());
             res_sqlite(r)?;
+            let r = tran.execute(
+                r#"create table if not exists trace_result (
+                        trace_id text not null primary key,
+                        status text not null,
+                        failing_step integer,
+                        duration_millis integer not null,
+                        timestamp integer not null,
+                        error text
+                        )"#, ());
+            res_sqlite(r)?;
+            let r = tran.execute(
+                r#"create table if not exists recorded_action (
+                        run_id text not null,
+                        seq integer not null,
+                        timestamp_micros integer not null,
+                        node_id integer not null,
+                        begin integer not null,
+                        action_json text not null,
+                        primary key (run_id, seq)
+                        )"#, ());
+            res_sqlite(r)?;
             tran.commit().unwrap();
         }
         let r = Self {
@@ -50,6 +101,129 @@ impl TraceDB {
         Ok(())
     }
 
+    /// Read (trace id, trace json) pairs ordered by id
+    pub fn read_trace_with_id(&self) -> Res<Vec<(String, String)>> {
+        let sql = "select id, trace_json from trace order by id";
+        let mut conn = self.conn.lock().unwrap();
+        let trans = res_sqlite(conn.transaction())?;
+        let mut stmt = res_sqlite(trans.prepare(sql))?;
+        let mut rows = res_sqlite(stmt.query(()))?;
+        let mut vec = vec![];
+        while let Some(row) = res_sqlite(rows.next())? {
+            let id: String = res_sqlite(row.get(0))?;
+            let json_value: String = res_sqlite(row.get(1))?;
+            vec.push((id, json_value));
+        }
+        Ok(vec)
+    }
+
+    /// Write the result of a trace, replace the result of the previous run
+    pub fn write_trace_result(&self, result: &TraceResult) -> Res<()> {
+        let sql = r#"insert into trace_result
+            (trace_id, status, failing_step, duration_millis, timestamp, error)
+            values (?1, ?2, ?3, ?4, ?5, ?6)
+            on conflict (trace_id) do update set
+                status = excluded.status,
+                failing_step = excluded.failing_step,
+                duration_millis = excluded.duration_millis,
+                timestamp = excluded.timestamp,
+                error = excluded.error;"#;
+        let conn = self.conn.lock().unwrap();
+        let r = conn.execute(sql, (
+            result.trace_id.clone(),
+            result.status.clone(),
+            result.failing_step.map(|n| { n as i64 }),
+            result.duration_millis as i64,
+            result.timestamp as i64,
+            result.error.clone(),
+        ));
+        res_sqlite(r)?;
+        Ok(())
+    }
+
+    /// Read the results of the traces ordered by trace id
+    pub fn read_trace_result(&self) -> Res<Vec<TraceResult>> {
+        let sql = r#"select trace_id, status, failing_step, duration_millis, timestamp, error
+            from trace_result order by trace_id"#;
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = res_sqlite(conn.prepare(sql))?;
+        let mut rows = res_sqlite(stmt.query(()))?;
+        let mut vec = vec![];
+        while let Some(row) = res_sqlite(rows.next())? {
+            let failing_step: Option<i64> = res_sqlite(row.get(2))?;
+            let duration_millis: i64 = res_sqlite(row.get(3))?;
+            let timestamp: i64 = res_sqlite(row.get(4))?;
+            vec.push(TraceResult {
+                trace_id: res_sqlite(row.get(0))?,
+                status: res_sqlite(row.get(1))?,
+                failing_step: failing_step.map(|n| { n as u64 }),
+                duration_millis: duration_millis as u64,
+                timestamp: timestamp as u64,
+                error: res_sqlite(row.get(5))?,
+            });
+        }
+        Ok(vec)
+    }
+
+    pub fn write_recorded_action(&self, action: &RecordedAction) -> Res<()> {
+        let sql = r#"insert into recorded_action
+            (run_id, seq, timestamp_micros, node_id, begin, action_json)
+            values (?1, ?2, ?3, ?4, ?5, ?6)
+            on conflict (run_id, seq) do nothing;"#;
+        let conn = self.conn.lock().unwrap();
+        let r = conn.execute(sql, (
+            action.run_id.clone(),
+            action.seq as i64,
+            action.timestamp_micros as i64,
+            action.node_id as i64,
+            action.begin,
+            action.action.to_string(),
+        ));
+        res_sqlite(r)?;
+        Ok(())
+    }
+
+    /// Read the actions recorded in a run in arrival order
+    pub fn read_recorded_action(&self, run_id: &str) -> Res<Vec<RecordedAction>> {
+        let sql = r#"select run_id, seq, timestamp_micros, node_id, begin, action_json
+            from recorded_action where run_id = ?1 order by seq"#;
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = res_sqlite(conn.prepare(sql))?;
+        let mut rows = res_sqlite(stmt.query((run_id.to_string(),)))?;
+        let mut vec = vec![];
+        while let Some(row) = res_sqlite(rows.next())? {
+            let seq: i64 = res_sqlite(row.get(1))?;
+            let timestamp_micros: i64 = res_sqlite(row.get(2))?;
+            let node_id: i64 = res_sqlite(row.get(3))?;
+            let action_json: String = res_sqlite(row.get(5))?;
+            vec.push(RecordedAction {
+                run_id: res_sqlite(row.get(0))?,
+                seq: seq as u64,
+                timestamp_micros: timestamp_micros as u64,
+                node_id: node_id as u64,
+                begin: res_sqlite(row.get(4))?,
+                action: res_parse(serde_json::from_str(action_json.as_str()))?,
+            });
+        }
+        Ok(vec)
+    }
+
+    /// Convert a recorded run to a trace with id `run_id`, which the player can replay.
+    /// Each action appears once, at the point the player synchronizes on it: the begin of an
+    /// Output or Internal action, and the end of a Setup, Check or Input action.
+    pub fn write_recorded_trace(&self, run_id: &str) -> Res<usize> {
+        let mut trace: Vec<Value> = vec![];
+        for a in self.read_recorded_action(run_id)? {
+            let action_type = ActionJson::from_value(a.action.clone()).action_type()?;
+            if a.begin != action_type.is_delivered_to_node() {
+                trace.push(a.action);
+            }
+        }
+        let n = trace.len();
+        self.write_trace(vec![(run_id.to_string(), trace)])?;
+        Ok(n)
+    }
+
     pub fn read_trace(&self) -> Res<Vec<String>> {
         let sql = "select trace_json from trace order by id";
         let mut conn = self.conn.lock().unwrap();
@@ -63,4 +237,71 @@ impl TraceDB {
         }
         Ok(vec)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::trace::trace_db::{RecordedAction, TraceDB, TraceResult};
+    use crate::util::tmp::tmp_dir;
+
+    #[test]
+    fn test_trace_result() {
+        let path = tmp_dir(format!("trace_{}.db", Uuid::new_v4()));
+        let db = TraceDB::new(path.clone()).unwrap();
+        db.write_trace(vec![
+            ("t2".to_string(), vec![json!(2)]),
+            ("t1".to_string(), vec![json!(1)]),
+        ]).unwrap();
+        let traces = db.read_trace_with_id().unwrap();
+        assert_eq!(traces.len(), 2);
+        assert_eq!(traces[0].0, "t1");
+
+        let mut result = TraceResult {
+            trace_id: "t1".to_string(),
+            status: "timeout".to_string(),
+            failing_step: Some(3),
+            duration_millis: 100,
+            timestamp: 1,
+            error: Some("timeout at No.3".to_string()),
+        };
+        db.write_trace_result(&result).unwrap();
+        result.status = "passed".to_string();
+        result.failing_step = None;
+        result.error = None;
+        db.write_trace_result(&result).unwrap();
+        assert_eq!(db.read_trace_result().unwrap(), vec![result]);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_recorded_action() {
+        let path = tmp_dir(format!("record_{}.db", Uuid::new_v4()));
+        let db = TraceDB::new(path.clone()).unwrap();
+        let internal = json!({"Internal": {"source": 1, "dest": 1, "payload": {"Tick": 1}}});
+        let input = json!({"Input": {"source": 2, "dest": 1, "payload": {"Request": 1}}});
+        // an Input action is reported by an end action only
+        let actions = [(1, true, internal.clone()), (2, false, internal.clone()), (3, false, input.clone())];
+        for (seq, begin, action) in actions {
+            db.write_recorded_action(&RecordedAction {
+                run_id: "r1".to_string(),
+                seq,
+                timestamp_micros: 10 * seq,
+                node_id: 1,
+                begin,
+                action,
+            }).unwrap();
+        }
+        let recorded = db.read_recorded_action("r1").unwrap();
+        assert_eq!(recorded.len(), 3);
+        assert!(!recorded[2].begin);
+        assert_eq!(db.write_recorded_trace("r1").unwrap(), 2);
+        let traces = db.read_trace_with_id().unwrap();
+        assert_eq!(traces[0].0, "r1");
+        let trace: Vec<serde_json::Value> = serde_json::from_str(traces[0].1.as_str()).unwrap();
+        assert_eq!(trace, vec![internal, input]);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -12,6 +12,7 @@ use scupt_util::res_of::{res_parse, res_sqlite};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::action::action_json::ActionJson;
 use crate::trace::action_graph::ActionGraph;
 use crate::trace::graph_util::adj_add_new_edge;
 
@@ -247,6 +248,86 @@ impl TraceDBInterm {
         Ok(())
     }
 
+    /// Read the rows of the action table, (id, prev_id, the states of id, the actions from state
+    /// prev_id to id)
+    pub fn read_action<F>(&self, f_handle_action: &mut F) -> Res<()>
+        where F: FnMut(i64, i64, Vec<Value>, Vec<Value>) -> Res<()>
+    {
+        let sql = r#"select id, prev_id, state_json, action_json from action order by id;"#;
+        let conn = self.conn.lock().unwrap();
+        let stmt_r = conn.prepare(sql);
+        let mut stmt = res_sqlite(stmt_r)?;
+        let mut rows = res_sqlite(stmt.query(()))?;
+        while let Some(row) = res_sqlite(rows.next())? {
+            let id: i64 = res_sqlite(row.get(0))?;
+            let prev_id: i64 = res_sqlite(row.get(1))?;
+            let mut vec = vec![];
+            for i in [2, 3] {
+                let json: String = res_sqlite(row.get(i))?;
+                let value: Value = res_parse(serde_json::from_str(json.as_str()))?;
+                vec.push(match value {
+                    Value::Array(vec) => {
+                        vec
+                    }
+                    _ => {
+                        vec![value]
+                    }
+                });
+            }
+            let actions = vec.pop().unwrap();
+            let states = vec.pop().unwrap();
+            f_handle_action(id, prev_id, states, actions)?;
+        }
+        Ok(())
+    }
+
+    /// Read the edges of the action table, state id -> (prev_id, the names of the actions from
+    /// state prev_id to id)
+    pub fn read_action_names(&self) -> Res<HashMap<i64, (i64, Vec<String>)>> {
+        let mut edges = HashMap::new();
+        let mut f_action = |id: i64, prev_id: i64, _states: Vec<Value>, actions: Vec<Value>| -> Res<()> {
+            let mut names = vec![];
+            for v in actions {
+                names.push(ActionJson::from_value(v).action_name()?);
+            }
+            edges.insert(id, (prev_id, names));
+            Ok(())
+        };
+        self.read_action(&mut f_action)?;
+        Ok(edges)
+    }
+
+    /// Read the paths, (path id, the action ids in the path from the initial state)
+    pub fn read_path<F>(&self, f_handle_path: &mut F) -> Res<()>
+        where F: FnMut(String, Vec<i64>) -> Res<()>
+    {
+        let sql = r#"select id, action_id from path order by id, seq;"#;
+        let conn = self.conn.lock().unwrap();
+        let stmt_r = conn.prepare(sql);
+        let mut stmt = res_sqlite(stmt_r)?;
+        let mut rows = res_sqlite(stmt.query(()))?;
+        let mut opt_path: Option<(String, Vec<i64>)> = None;
+        while let Some(row) = res_sqlite(rows.next())? {
+            let path_id: String = res_sqlite(row.get(0))?;
+            let action_id: i64 = res_sqlite(row.get(1))?;
+            match &mut opt_path {
+                Some((id, vec)) if *id == path_id => {
+                    vec.push(action_id);
+                }
+                _ => {
+                    if let Some((id, vec)) = opt_path.take() {
+                        f_handle_path(id, vec)?;
+                    }
+                    opt_path = Some((path_id, vec![action_id]));
+                }
+            }
+        }
+        if let Some((id, vec)) = opt_path {
+            f_handle_path(id, vec)?;
+        }
+        Ok(())
+    }
+
     pub fn state<F>(&self, f_handle_state: &F) -> Res<()>
         where F: Fn(String, Vec<Value>)
     {
@@ -0,0 +1,278 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use scupt_util::message::MsgTrait;
+use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+use serde_json::Value;
+
+use crate::action::action_json::ActionJson;
+use crate::action::action_type::ActionType;
+use crate::action::res_serde::res_serde;
+use crate::action::trace::{Trace, TraceJsonValue};
+use crate::dtm::trace_verdict::TraceVerdict;
+
+/// The output format of a sequence diagram
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum DiagramFormat {
+    Mermaid,
+    #[value(name = "plantuml")]
+    PlantUml,
+    Dot,
+}
+
+enum StepKind {
+    // a message from a node to another node
+    Message { source: NID, dest: NID, input: bool },
+    // an action inside a node, or a fault on some nodes
+    Note { nodes: Vec<NID> },
+}
+
+struct DiagramStep {
+    seq_no: u64,
+    kind: StepKind,
+    label: String,
+}
+
+/// A message sequence chart of a trace, which has one lifeline for each node.
+/// Setup, Check, Input and Output actions are arrows from the source to the dest, Internal and
+/// Fault actions are notes.
+pub struct TraceDiagram {
+    nodes: BTreeSet<NID>,
+    steps: Vec<DiagramStep>,
+    failing_step: Option<u64>,
+}
+
+impl TraceDiagram {
+    pub fn from_trace_json(trace: &TraceJsonValue) -> Res<Self> {
+        let mut nodes = BTreeSet::new();
+        let mut steps = vec![];
+        for (i, v) in trace.actions.iter().enumerate() {
+            let action = ActionJson::from_value(v.clone());
+            let source = action.source_nid()?;
+            let dest = action.dest_nid()?;
+            nodes.insert(source);
+            nodes.insert(dest);
+            let name = action.action_name()?;
+            let (kind, label) = match action.action_type()? {
+                ActionType::Setup | ActionType::Check => {
+                    let t = action.action_type()?.to_string();
+                    (StepKind::Message { source, dest, input: true }, format!("{} {}", t, name))
+                }
+                ActionType::Input => {
+                    (StepKind::Message { source, dest, input: true }, name)
+                }
+                ActionType::Output => {
+                    (StepKind::Message { source, dest, input: false }, name)
+                }
+                ActionType::Internal => {
+                    (StepKind::Note { nodes: vec![dest] }, name)
+                }
+                ActionType::Fault => {
+                    let nodes = if source == dest { vec![source] } else { vec![source, dest] };
+                    (StepKind::Note { nodes }, format!("Fault {}", name))
+                }
+            };
+            steps.push(DiagramStep {
+                seq_no: i as u64 + 1,
+                kind,
+                label: format!("No.{} {}", i + 1, label),
+            });
+        }
+        Ok(Self {
+            nodes,
+            steps,
+            failing_step: None,
+        })
+    }
+
+    pub fn from_trace<M: MsgTrait + 'static>(trace: &Trace<M>) -> Res<Self> {
+        let value: Value = res_serde(serde_json::to_value(trace))?;
+        Self::from_trace_json(&TraceJsonValue::from_json_value(value)?)
+    }
+
+    /// Highlight the step, whose sequence number starts from 1
+    pub fn set_failing_step(self, seq_no: u64) -> Self {
+        let mut s = self;
+        s.failing_step = Some(seq_no);
+        s
+    }
+
+    /// Highlight the step at which the trace failed
+    pub fn set_verdict(self, verdict: &TraceVerdict) -> Self {
+        match verdict.step() {
+            Some(step) => { self.set_failing_step(step.seq_no) }
+            None => { self }
+        }
+    }
+
+    pub fn render(&self, format: DiagramFormat) -> String {
+        let mut s = String::new();
+        // writing to a String never fails
+        let _ = match format {
+            DiagramFormat::Mermaid => { self.write_mermaid(&mut s) }
+            DiagramFormat::PlantUml => { self.write_plantuml(&mut s) }
+            DiagramFormat::Dot => { self.write_dot(&mut s) }
+        };
+        s
+    }
+
+    fn is_failing(&self, step: &DiagramStep) -> bool {
+        self.failing_step == Some(step.seq_no)
+    }
+
+    fn write_mermaid(&self, s: &mut String) -> std::fmt::Result {
+        writeln!(s, "sequenceDiagram")?;
+        for n in self.nodes.iter() {
+            writeln!(s, "    participant n{} as node {}", n, n)?;
+        }
+        for step in self.steps.iter() {
+            let failing = self.is_failing(step);
+            if failing {
+                writeln!(s, "    rect rgb(255, 200, 200)")?;
+            }
+            let label = escape(&step.label, &['#', ';']);
+            match &step.kind {
+                StepKind::Message { source, dest, input } => {
+                    let arrow = if *input { "->>" } else { "-->>" };
+                    writeln!(s, "    n{}{}n{}: {}", source, arrow, dest, label)?;
+                }
+                StepKind::Note { nodes } => {
+                    let over: Vec<String> = nodes.iter().map(|n| { format!("n{}", n) }).collect();
+                    writeln!(s, "    Note over {}: {}", over.join(","), label)?;
+                }
+            }
+            if failing {
+                writeln!(s, "    Note over {}: failed here", self.step_lifelines(step, "n", ","))?;
+                writeln!(s, "    end")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_plantuml(&self, s: &mut String) -> std::fmt::Result {
+        writeln!(s, "@startuml")?;
+        for n in self.nodes.iter() {
+            writeln!(s, "participant \"node {}\" as n{}", n, n)?;
+        }
+        for step in self.steps.iter() {
+            let failing = self.is_failing(step);
+            match &step.kind {
+                StepKind::Message { source, dest, input } => {
+                    let line = if *input { "-" } else { "--" };
+                    let color = if failing { "[#red]" } else { "" };
+                    writeln!(s, "n{} {}{}> n{}: {}", source, line, color, dest, step.label)?;
+                }
+                StepKind::Note { nodes } => {
+                    let over: Vec<String> = nodes.iter().map(|n| { format!("n{}", n) }).collect();
+                    let color = if failing { " #pink" } else { "" };
+                    writeln!(s, "note over {}{}: {}", over.join(", "), color, step.label)?;
+                }
+            }
+            if failing {
+                writeln!(s, "note over {} #red: failed here", self.step_lifelines(step, "n", ", "))?;
+            }
+        }
+        writeln!(s, "@enduml")?;
+        Ok(())
+    }
+
+    // each step is a row ranked the same, each lifeline is a column of points
+    fn write_dot(&self, s: &mut String) -> std::fmt::Result {
+        writeln!(s, "digraph trace {{")?;
+        writeln!(s, "    rankdir=TB;")?;
+        writeln!(s, "    node [shape=point];")?;
+        let row = |i: usize| -> String {
+            let names: Vec<String> = self.nodes.iter().map(|n| { format!("n{}_{}", n, i) }).collect();
+            format!("    {{ rank=same; {}; }}", names.join("; "))
+        };
+        for n in self.nodes.iter() {
+            writeln!(s, "    n{}_0 [shape=box, label=\"node {}\"];", n, n)?;
+        }
+        writeln!(s, "{}", row(0))?;
+        for (i, step) in self.steps.iter().enumerate() {
+            let i = i + 1;
+            let color = if self.is_failing(step) { "red" } else { "black" };
+            let label = escape(&step.label, &['"']);
+            match &step.kind {
+                StepKind::Message { source, dest, input } => {
+                    let style = if *input { "solid" } else { "dashed" };
+                    writeln!(s, "    n{}_{} -> n{}_{} [label=\"{}\", style={}, color={}, fontcolor={}, constraint=false];",
+                             source, i, dest, i, label, style, color, color)?;
+                }
+                StepKind::Note { nodes } => {
+                    for n in nodes {
+                        writeln!(s, "    n{}_{} [shape=note, label=\"{}\", color={}, fontcolor={}];",
+                                 n, i, label, color, color)?;
+                    }
+                }
+            }
+            writeln!(s, "{}", row(i))?;
+        }
+        // the lifelines
+        for n in self.nodes.iter() {
+            for i in 0..self.steps.len() {
+                writeln!(s, "    n{}_{} -> n{}_{} [style=dotted, arrowhead=none];", n, i, n, i + 1)?;
+            }
+        }
+        writeln!(s, "}}")?;
+        Ok(())
+    }
+
+    fn step_lifelines(&self, step: &DiagramStep, prefix: &str, separator: &str) -> String {
+        let nodes = match &step.kind {
+            StepKind::Message { source, dest, .. } => {
+                if source == dest { vec![*source] } else { vec![*source, *dest] }
+            }
+            StepKind::Note { nodes } => { nodes.clone() }
+        };
+        let vec: Vec<String> = nodes.iter().map(|n| { format!("{}{}", prefix, n) }).collect();
+        vec.join(separator)
+    }
+}
+
+fn escape(label: &str, chars: &[char]) -> String {
+    let mut s = String::new();
+    for c in label.chars() {
+        if chars.contains(&c) {
+            s.push('\\');
+        }
+        s.push(c);
+    }
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+
+    use crate::action::trace::TraceJsonValue;
+    use crate::trace::trace_diagram::{DiagramFormat, TraceDiagram};
+
+    #[test]
+    fn test_trace_diagram() {
+        let trace = TraceJsonValue::from_json_value(Value::Array(vec![
+            json!({"Input": {"source": 1, "dest": 2, "payload": {"Request": 1}}}),
+            json!({"Internal": {"source": 2, "dest": 2, "payload": {"Handle": 1}}}),
+            json!({"Output": {"source": 2, "dest": 1, "payload": {"Response": 1}}}),
+        ])).unwrap();
+        let diagram = TraceDiagram::from_trace_json(&trace).unwrap().set_failing_step(3);
+
+        let mermaid = diagram.render(DiagramFormat::Mermaid);
+        assert!(mermaid.starts_with("sequenceDiagram\n"));
+        assert!(mermaid.contains("    n1->>n2: No.1 Request\n"));
+        assert!(mermaid.contains("    Note over n2: No.2 Handle\n"));
+        assert!(mermaid.contains("    rect rgb(255, 200, 200)\n    n2-->>n1: No.3 Response\n"));
+
+        let plantuml = diagram.render(DiagramFormat::PlantUml);
+        assert!(plantuml.contains("n1 -> n2: No.1 Request\n"));
+        assert!(plantuml.contains("n2 --[#red]> n1: No.3 Response\n"));
+        assert!(plantuml.ends_with("@enduml\n"));
+
+        let dot = diagram.render(DiagramFormat::Dot);
+        assert!(dot.starts_with("digraph trace {\n"));
+        assert!(dot.contains("n2_2 [shape=note, label=\"No.2 Handle\""));
+        assert!(dot.contains("n2_3 -> n1_3 [label=\"No.3 Response\", style=dashed, color=red"));
+        assert!(dot.contains("n1_2 -> n1_3 [style=dotted, arrowhead=none];"));
+    }
+}
@@ -2,8 +2,11 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
+use crate::trace::action_graph::{OptPath, PathStrategy};
 use crate::trace::gen_case::{DataInput, gen_case};
+use crate::trace::path_filter::PathFilter;
 use crate::trace::read_json;
+use crate::trace::trace_builder::OptBuild;
 
 #[derive(clap::ValueEnum, Clone, Debug)]
 enum TestType {
@@ -46,6 +49,47 @@ pub struct GenArgs {
     /// Generate action DB only, default value is false, otherwise only generate trace
     #[arg(short = 't', long, default_value = "false")]
     action_only: bool,
+
+    /// The strategy to generate paths of the action graph, dfs, edge-cover, random-walk or scc
+    #[arg(long, value_enum, default_value = "dfs")]
+    strategy: PathStrategy,
+
+    /// The number of random walks, for the random-walk strategy
+    #[arg(long, default_value = "100")]
+    count: u64,
+
+    /// The max number of actions of a random walk, for the random-walk strategy
+    #[arg(long, default_value = "100")]
+    max_len: u64,
+
+    /// The seed of the random walks, for the random-walk strategy
+    #[arg(long, default_value = "0")]
+    seed: u64,
+
+    /// Pick the successors of a random walk with weights favoring the edges less walked,
+    /// default value is false, the successors are picked uniformly
+    #[arg(long, default_value = "false")]
+    weighted: bool,
+
+    /// The number of times a path walks the cycles of a strongly connected component, for the
+    /// scc strategy
+    #[arg(long, default_value = "1")]
+    unroll: u64,
+
+    /// Generate only the traces performing an action whose name matches the regex, such as
+    /// `Raft::StepDown`, can be repeated
+    #[arg(long)]
+    require_action: Vec<String>,
+
+    /// Generate only the traces performing no action whose name matches the regex, can be
+    /// repeated
+    #[arg(long)]
+    forbid_action: Vec<String>,
+
+    /// Generate only the traces performing an action matching regex A and later an action
+    /// matching regex B, written as `--require-sequence A B`, can be repeated
+    #[arg(long, num_args = 2, value_names = ["A", "B"])]
+    require_sequence: Vec<String>,
 }
 
 
@@ -60,6 +104,13 @@ pub fn portal(args: GenArgs) {
     let intermediate_db_path: Option<String> = args.out_action_db_path;
     let setup_initialize_state: bool = args.setup_initialize_state;
     let sqlite_cache_size = args.sqlite_cache_size;
+    let sequence: Vec<(String, String)> = args.require_sequence.chunks(2)
+        .map(|p| { (p[0].clone(), p[1].clone()) })
+        .collect();
+    let filter = match PathFilter::new(&args.require_action, &args.forbid_action, &sequence) {
+        Ok(filter) => { filter }
+        Err(e) => { panic!("action pattern error: {}", e); }
+    };
     let path_input = if state_db_path.is_some() {
         DataInput::StateDB(state_db_path.unwrap())
     } else {
@@ -92,9 +143,20 @@ pub fn portal(args: GenArgs) {
         path_output,
         dict,
         intermediate_db_path,
-        sqlite_cache_size,
-        setup_initialize_state,
-        true
+        OptBuild {
+            initialize_setup: setup_initialize_state,
+            sqlite_cache_size,
+        },
+        true,
+        OptPath {
+            strategy: args.strategy,
+            count: args.count,
+            max_len: args.max_len,
+            seed: args.seed,
+            weighted: args.weighted,
+            unroll: args.unroll,
+            filter,
+        },
     ).unwrap();
 }
 
@@ -106,6 +168,7 @@ mod test {
 
     use crate::data::path::_test::_test_data_path;
     use crate::util::tmp::tmp_dir;
+    use crate::trace::action_graph::PathStrategy;
     use crate::trace::trace_gen_portal::{GenArgs, portal};
 
     #[test]
@@ -121,6 +184,15 @@ mod test {
             setup_initialize_state: false,
             sqlite_cache_size: None,
             action_only: false,
+            strategy: PathStrategy::Dfs,
+            count: 100,
+            max_len: 100,
+            seed: 0,
+            weighted: false,
+            unroll: 1,
+            require_action: vec![],
+            forbid_action: vec![],
+            require_sequence: vec![],
         };
         portal(args);
     }
@@ -139,6 +211,15 @@ mod test {
             setup_initialize_state: false,
             sqlite_cache_size: None,
             action_only: false,
+            strategy: PathStrategy::Dfs,
+            count: 100,
+            max_len: 100,
+            seed: 0,
+            weighted: false,
+            unroll: 1,
+            require_action: vec![],
+            forbid_action: vec![],
+            require_sequence: vec![],
         };
         portal(args);
     }
@@ -0,0 +1,191 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+use scupt_util::node_id::NID;
+use scupt_util::res::Res;
+
+use crate::action::action_json::ActionJson;
+use crate::action::action_type::ActionType;
+use crate::action::trace::TraceJsonValue;
+use crate::trace::spec_graph::{SpecCursor, SpecGraph};
+
+/// The result of validating a trace against the state graph of a specification
+#[derive(Clone, Debug)]
+pub struct TraceValidation {
+    /// the number of actions in the trace
+    pub num_actions: usize,
+    /// the number of actions of the longest prefix which is a path of the graph, when
+    /// interleaving is allowed, the prefix consists of a prefix of the actions of each node
+    pub matched: usize,
+    /// the first action(index start from 0, action) of the trace not in the prefix
+    pub first_unmatched: Option<(usize, ActionJson)>,
+    /// the states of the graph reachable by the prefix
+    pub reachable_states: Vec<i64>,
+    /// the actions which can be performed after the prefix
+    pub enabled: Vec<ActionJson>,
+}
+
+impl TraceValidation {
+    /// The whole trace is a path of the graph
+    pub fn is_valid(&self) -> bool {
+        self.matched == self.num_actions
+    }
+}
+
+impl fmt::Display for TraceValidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "valid, {} actions are a path of the spec graph", self.num_actions);
+        }
+        writeln!(f, "invalid, the longest prefix matched has {} of {} actions",
+                 self.matched, self.num_actions)?;
+        if let Some((i, a)) = &self.first_unmatched {
+            writeln!(f, "first action not matched: No.{} {}", i + 1, a.serde_json_value_ref())?;
+        }
+        writeln!(f, "spec states reachable: {:?}", self.reachable_states)?;
+        write!(f, "spec actions enabled:")?;
+        for a in self.enabled.iter() {
+            write!(f, "\n  {}", a.serde_json_value_ref())?;
+        }
+        Ok(())
+    }
+}
+
+// a prefix explored, the number of the actions of each node in the prefix, and the cursor after
+// the prefix
+type Prefix = (Vec<usize>, SpecCursor);
+
+/// Decide whether a trace is a path of the state graph.
+///
+/// When `interleave` is enabled, the actions of different nodes can be performed in an order
+/// other than the trace order, only the order of the actions of each node is kept.
+/// Otherwise, the actions must be performed in the trace order.
+pub fn validate_trace(
+    graph: Arc<SpecGraph>,
+    trace: &TraceJsonValue,
+    interleave: bool,
+) -> Res<TraceValidation> {
+    let mut actions = vec![];
+    for v in trace.actions.iter() {
+        actions.push(ActionJson::from_value(v.clone()));
+    }
+    // the indices of the actions of each node in the trace
+    let mut per_node: BTreeMap<NID, Vec<usize>> = BTreeMap::new();
+    for (i, a) in actions.iter().enumerate() {
+        let nid = if interleave { action_node(a)? } else { 0 };
+        per_node.entry(nid).or_default().push(i);
+    }
+    let queues: Vec<Vec<usize>> = per_node.into_values().collect();
+
+    let init: Prefix = (vec![0; queues.len()], SpecCursor::new(graph));
+    let mut best = init.clone();
+    let mut visited: HashSet<Prefix> = HashSet::new();
+    let mut stack = vec![init];
+    while let Some(prefix) = stack.pop() {
+        if !visited.insert(prefix.clone()) {
+            continue;
+        }
+        let (counts, cursor) = prefix;
+        if num_matched(&counts) > num_matched(&best.0) {
+            best = (counts.clone(), cursor.clone());
+            if num_matched(&counts) == actions.len() {
+                break;
+            }
+        }
+        // the next action of each node, the earliest one in the trace is tried at first
+        let mut next: Vec<(usize, usize)> = counts.iter().enumerate()
+            .filter_map(|(q, n)| { queues[q].get(*n).map(|i| { (*i, q) }) })
+            .collect();
+        next.sort_by(|a, b| { b.cmp(a) });
+        for (i, q) in next {
+            let mut c = cursor.clone();
+            if c.step(&actions[i]) {
+                let mut counts = counts.clone();
+                counts[q] += 1;
+                stack.push((counts, c));
+            }
+        }
+    }
+
+    let (counts, cursor) = best;
+    let first_unmatched = counts.iter().enumerate()
+        .filter_map(|(q, n)| { queues[q].get(*n).cloned() })
+        .min()
+        .map(|i| { (i, actions[i].clone()) });
+    Ok(TraceValidation {
+        num_actions: actions.len(),
+        matched: num_matched(&counts),
+        first_unmatched,
+        reachable_states: cursor.states(),
+        enabled: cursor.enabled(),
+    })
+}
+
+fn num_matched(counts: &[usize]) -> usize {
+    counts.iter().sum()
+}
+
+// the node performs the action
+fn action_node(action: &ActionJson) -> Res<NID> {
+    let nid = match action.action_type()? {
+        ActionType::Setup | ActionType::Check | ActionType::Input | ActionType::Internal => {
+            action.dest_nid()?
+        }
+        ActionType::Output | ActionType::Fault => { action.source_nid()? }
+    };
+    Ok(nid)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use serde_json::{json, Value};
+
+    use crate::action::action_json::ActionJson;
+    use crate::action::trace::TraceJsonValue;
+    use crate::trace::spec_graph::SpecGraph;
+    use crate::trace::trace_validation::validate_trace;
+
+    fn action(nid: u64, name: &str) -> Value {
+        json!({"Internal": {"source": nid, "dest": nid, "payload": {name: 1}}})
+    }
+
+    fn trace(actions: Vec<Value>) -> TraceJsonValue {
+        TraceJsonValue::from_json_value(Value::Array(actions)).unwrap()
+    }
+
+    #[test]
+    fn test_validate_trace() {
+        let a = |nid, name| { ActionJson::from_value(action(nid, name)) };
+        // 1 -a1-> 2 -b2-> 3 -c1-> 4
+        let graph = Arc::new(SpecGraph::new(vec![
+            (1, 0, vec![a(1, "a")]),
+            (2, 1, vec![a(2, "b")]),
+            (3, 2, vec![a(1, "c")]),
+        ]));
+
+        let t = trace(vec![action(1, "a"), action(2, "b"), action(1, "c")]);
+        let r = validate_trace(graph.clone(), &t, false).unwrap();
+        assert!(r.is_valid());
+        assert_eq!(r.reachable_states, vec![3]);
+
+        // node 1 runs c before node 2 runs b, which is another interleaving of the path
+        let t = trace(vec![action(1, "a"), action(1, "c"), action(2, "b")]);
+        let r = validate_trace(graph.clone(), &t, false).unwrap();
+        assert!(!r.is_valid());
+        assert_eq!(r.matched, 1);
+        assert_eq!(r.first_unmatched.as_ref().unwrap().0, 1);
+        assert_eq!(r.reachable_states, vec![1]);
+        assert_eq!(r.enabled, vec![a(2, "b")]);
+        assert!(validate_trace(graph.clone(), &t, true).unwrap().is_valid());
+
+        // the order of the actions of node 1 is kept
+        let t = trace(vec![action(1, "c"), action(2, "b"), action(1, "a")]);
+        let r = validate_trace(graph, &t, true).unwrap();
+        assert_eq!(r.matched, 0);
+        assert_eq!(r.first_unmatched.as_ref().unwrap().0, 0);
+        assert!(r.to_string().starts_with("invalid, the longest prefix matched has 0 of 3 actions"));
+    }
+}
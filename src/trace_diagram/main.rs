@@ -0,0 +1,79 @@
+use std::fs::{read_to_string, write};
+
+use clap::Parser;
+use scupt_util::error_type::ET;
+use scupt_util::init_logger::logger_setup;
+use scupt_util::res::Res;
+use scupt_util::res_of::{res_io, res_option};
+use sedeve_kit::action::trace::TraceJsonValue;
+use sedeve_kit::trace::trace_db::TraceDB;
+use sedeve_kit::trace::trace_diagram::{DiagramFormat, TraceDiagram};
+
+/// Render a trace as a sequence diagram, with one lifeline for each node
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path of a JSON trace file
+    #[arg(short = 'f', long)]
+    trace_path: Option<String>,
+
+    /// Path of a trace database, the trace is read from it by `--trace-id`, and the failing step
+    /// recorded in its results is highlighted
+    #[arg(short = 'd', long)]
+    trace_db_path: Option<String>,
+
+    /// Id of the trace in the trace database
+    #[arg(short = 'i', long)]
+    trace_id: Option<String>,
+
+    /// Highlight this step, whose sequence number starts from 1
+    #[arg(short = 's', long)]
+    failing_step: Option<u64>,
+
+    /// Format of the diagram
+    #[arg(short = 'm', long, value_enum, default_value_t = DiagramFormat::Mermaid)]
+    format: DiagramFormat,
+
+    /// Write the diagram to this path, otherwise, print it
+    #[arg(short, long)]
+    output_path: Option<String>,
+}
+
+fn diagram(args: &Args) -> Res<String> {
+    let mut failing_step = args.failing_step;
+    let json = match (&args.trace_path, &args.trace_db_path, &args.trace_id) {
+        (Some(path), _, _) => { res_io(read_to_string(path))? }
+        (None, Some(path), Some(trace_id)) => {
+            let db = TraceDB::new(path.clone())?;
+            if failing_step.is_none() {
+                failing_step = db.read_trace_result()?
+                    .into_iter()
+                    .find(|r| { r.trace_id == *trace_id })
+                    .and_then(|r| { r.failing_step });
+            }
+            let opt = db.read_trace_with_id()?
+                .into_iter()
+                .find(|(id, _)| { id == trace_id });
+            res_option(opt)?.1
+        }
+        _ => {
+            return Err(ET::NoneOption);
+        }
+    };
+    let trace = TraceJsonValue::from_json_string(json)?;
+    let mut diagram = TraceDiagram::from_trace_json(&trace)?;
+    if let Some(seq_no) = failing_step {
+        diagram = diagram.set_failing_step(seq_no);
+    }
+    Ok(diagram.render(args.format))
+}
+
+fn main() {
+    let args = Args::parse();
+    logger_setup();
+    let s = diagram(&args).unwrap();
+    match &args.output_path {
+        Some(path) => { res_io(write(path, s)).unwrap(); }
+        None => { print!("{}", s); }
+    }
+}
@@ -0,0 +1,83 @@
+use std::fs::read_to_string;
+use std::process::exit;
+use std::sync::Arc;
+
+use clap::Parser;
+use scupt_util::init_logger::logger_setup;
+use scupt_util::res::Res;
+use scupt_util::res_of::res_io;
+use sedeve_kit::action::trace::TraceJsonValue;
+use sedeve_kit::trace::spec_graph::SpecGraph;
+use sedeve_kit::trace::trace_db::TraceDB;
+use sedeve_kit::trace::trace_validation::validate_trace;
+
+/// Check whether traces, for example, the runs recorded from the implementation, are paths of
+/// the state graph of the specification
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path of the intermediate action database output by sedeve_trace_gen
+    #[arg(short, long)]
+    spec_db_path: String,
+
+    /// Path of a JSON trace file
+    #[arg(short = 'f', long)]
+    trace_path: Option<String>,
+
+    /// Path of a trace database, whose traces are validated
+    #[arg(short = 'd', long)]
+    trace_db_path: Option<String>,
+
+    /// Only validate the traces with these ids in the trace database
+    #[arg(short = 'i', long)]
+    trace_id: Vec<String>,
+
+    /// The actions must be in the trace order, otherwise, only the order of the actions of each
+    /// node is kept
+    #[arg(long, default_value_t = false)]
+    no_interleave: bool,
+}
+
+/// Return (trace id, trace json) of the traces to validate
+fn read_traces(args: &Args) -> Res<Vec<(String, String)>> {
+    let mut vec = vec![];
+    if let Some(path) = &args.trace_path {
+        vec.push((path.clone(), res_io(read_to_string(path))?));
+    }
+    if let Some(path) = &args.trace_db_path {
+        let db = TraceDB::new(path.clone())?;
+        for (id, json) in db.read_trace_with_id()? {
+            if args.trace_id.is_empty() || args.trace_id.contains(&id) {
+                vec.push((id, json));
+            }
+        }
+    }
+    Ok(vec)
+}
+
+/// Return the number of invalid traces
+fn validate(args: &Args) -> Res<usize> {
+    let graph = Arc::new(SpecGraph::from_db(args.spec_db_path.clone())?);
+    let traces = read_traces(args)?;
+    let mut num_invalid = 0;
+    for (id, json) in traces.iter() {
+        let trace = TraceJsonValue::from_json_string(json.clone())?;
+        let validation = validate_trace(graph.clone(), &trace, !args.no_interleave)?;
+        if !validation.is_valid() {
+            num_invalid += 1;
+        }
+        println!("trace {}: {}", id, validation);
+    }
+    println!("validate {} traces, {} valid, {} invalid",
+             traces.len(), traces.len() - num_invalid, num_invalid);
+    Ok(num_invalid)
+}
+
+fn main() {
+    let args = Args::parse();
+    logger_setup();
+    let num_invalid = validate(&args).unwrap();
+    if num_invalid > 0 {
+        exit(1);
+    }
+}